@@ -0,0 +1,197 @@
+//! Rastreadores de issues suportados e a interface comum entre eles
+
+use crate::error::{Error, Result};
+use crate::gitea::GiteaClient;
+use crate::gitlab::GitLabClient;
+use crate::models::{Group, Issue, IssueFilter, Note, Project, TimeStats, TokenInfo, User};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+
+/// Resultado de uma listagem condicional (`If-None-Match`)
+pub enum Fetched<T> {
+    NotModified,
+    Items(Vec<T>, Option<String>),
+}
+
+impl<T> Fetched<T> {
+    /// Itens da listagem (vazio se o servidor respondeu 304)
+    pub fn into_items(self) -> Vec<T> {
+        match self {
+            Fetched::Items(items, _) => items,
+            Fetched::NotModified => Vec::new(),
+        }
+    }
+}
+
+/// Operações que um rastreador de issues precisa oferecer ao app.
+///
+/// Listagens recebem o máximo de itens (`limit`) e o `etag` da última resposta,
+/// para o backend responder `Fetched::NotModified` quando nada mudou.
+pub trait TrackerBackend {
+    /// URL base da instância
+    fn base_url(&self) -> &str;
+
+    /// Usuário dono do token
+    fn current_user(&self) -> impl Future<Output = Result<User>> + Send;
+
+    /// Escopos e validade do token, se a instância informar
+    fn token_info(&self) -> impl Future<Output = Result<Option<TokenInfo>>> + Send;
+
+    /// Agrupadores de projetos: grupos no GitLab, organizações no Gitea
+    fn containers(&self, limit: usize, etag: Option<&str>) -> impl Future<Output = Result<Fetched<Group>>> + Send;
+
+    /// Projetos (repositórios) de um agrupador
+    fn projects(
+        &self,
+        container_id: i64,
+        limit: usize,
+        etag: Option<&str>,
+    ) -> impl Future<Output = Result<Fetched<Project>>> + Send;
+
+    /// Issues do projeto; `user_id` é o usuário salvo, usado em `assigned_to_me`
+    fn issues(
+        &self,
+        project_id: i64,
+        filter: &IssueFilter,
+        user_id: i64,
+        limit: usize,
+        etag: Option<&str>,
+    ) -> impl Future<Output = Result<Fetched<Issue>>> + Send;
+
+    /// Busca a issue no projeto a partir do `id` global, para sessões gravadas sem o `iid`
+    fn find_issue(&self, project_id: i64, issue_id: i64) -> impl Future<Output = Result<Issue>> + Send;
+
+    /// Lança `seconds` de tempo gasto na issue
+    fn log_time(
+        &self,
+        project_id: i64,
+        issue_iid: i64,
+        seconds: i64,
+        summary: &str,
+    ) -> impl Future<Output = Result<TimeStats>> + Send;
+
+    /// Tempo estimado e gasto na issue
+    fn time_stats(&self, project_id: i64, issue_iid: i64) -> impl Future<Output = Result<TimeStats>> + Send;
+
+    /// Comentários da issue
+    fn issue_notes(&self, project_id: i64, issue_iid: i64) -> impl Future<Output = Result<Vec<Note>>> + Send;
+}
+
+/// Tipo de rastreador configurado (coluna `config.backend_kind`)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    GitLab,
+    Gitea,
+}
+
+impl BackendKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BackendKind::GitLab => "gitlab",
+            BackendKind::Gitea => "gitea",
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "gitlab" => Ok(BackendKind::GitLab),
+            "gitea" => Ok(BackendKind::Gitea),
+            other => Err(Error::InvalidState(format!("Backend desconhecido: {}", other))),
+        }
+    }
+}
+
+/// Backend escolhido na configuração
+pub enum Backend {
+    GitLab(GitLabClient),
+    Gitea(GiteaClient),
+}
+
+impl Backend {
+    pub fn new(kind: BackendKind, http: &reqwest::Client, base_url: &str, token: &str) -> Self {
+        match kind {
+            BackendKind::GitLab => Backend::GitLab(GitLabClient::new(http, base_url, token)),
+            BackendKind::Gitea => Backend::Gitea(GiteaClient::new(http, base_url, token)),
+        }
+    }
+
+    pub fn kind(&self) -> BackendKind {
+        match self {
+            Backend::GitLab(_) => BackendKind::GitLab,
+            Backend::Gitea(_) => BackendKind::Gitea,
+        }
+    }
+}
+
+/// Repassa a chamada para o cliente do backend configurado
+macro_rules! dispatch {
+    ($self:ident, $client:ident => $call:expr) => {
+        match $self {
+            Backend::GitLab($client) => $call,
+            Backend::Gitea($client) => $call,
+        }
+    };
+}
+
+impl TrackerBackend for Backend {
+    fn base_url(&self) -> &str {
+        dispatch!(self, c => c.base_url())
+    }
+
+    async fn current_user(&self) -> Result<User> {
+        dispatch!(self, c => c.current_user().await)
+    }
+
+    async fn containers(&self, limit: usize, etag: Option<&str>) -> Result<Fetched<Group>> {
+        dispatch!(self, c => c.containers(limit, etag).await)
+    }
+
+    async fn token_info(&self) -> Result<Option<TokenInfo>> {
+        dispatch!(self, c => c.token_info().await)
+    }
+
+    async fn projects(&self, container_id: i64, limit: usize, etag: Option<&str>) -> Result<Fetched<Project>> {
+        dispatch!(self, c => c.projects(container_id, limit, etag).await)
+    }
+
+    async fn issues(
+        &self,
+        project_id: i64,
+        filter: &IssueFilter,
+        user_id: i64,
+        limit: usize,
+        etag: Option<&str>,
+    ) -> Result<Fetched<Issue>> {
+        dispatch!(self, c => c.issues(project_id, filter, user_id, limit, etag).await)
+    }
+
+    async fn find_issue(&self, project_id: i64, issue_id: i64) -> Result<Issue> {
+        dispatch!(self, c => c.find_issue(project_id, issue_id).await)
+    }
+
+    async fn log_time(&self, project_id: i64, issue_iid: i64, seconds: i64, summary: &str) -> Result<TimeStats> {
+        dispatch!(self, c => c.log_time(project_id, issue_iid, seconds, summary).await)
+    }
+
+    async fn time_stats(&self, project_id: i64, issue_iid: i64) -> Result<TimeStats> {
+        dispatch!(self, c => c.time_stats(project_id, issue_iid).await)
+    }
+
+    async fn issue_notes(&self, project_id: i64, issue_iid: i64) -> Result<Vec<Note>> {
+        dispatch!(self, c => c.issue_notes(project_id, issue_iid).await)
+    }
+}
@@ -0,0 +1,181 @@
+
+use crate::backend::Fetched;
+use crate::config;
+use crate::error::Result;
+use crate::models::IssueFilter;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Mutex;
+
+/// Tempo (segundos) em que uma listagem em cache é considerada atual
+const FRESH_FOR: i64 = 300;
+
+/// Tipo de listagem guardada (coluna `kind`)
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListKind {
+    Groups,
+    Projects,
+    Issues,
+}
+
+impl ListKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ListKind::Groups => "groups",
+            ListKind::Projects => "projects",
+            ListKind::Issues => "issues",
+        }
+    }
+}
+
+/// Listagem do rastreador guardada no SQLite
+pub struct CacheEntry<T> {
+    pub items: Vec<T>,
+    pub etag: Option<String>,
+    pub age: i64,
+}
+
+impl<T> CacheEntry<T> {
+    pub fn is_stale(&self) -> bool {
+        self.age >= FRESH_FOR
+    }
+}
+
+/// Chave da listagem: perfil, tipo, pai e filtros
+pub fn key(profile_id: i64, kind: ListKind, parent_id: Option<i64>, filter: Option<&IssueFilter>) -> String {
+    let mut key = format!("{}|{}", profile_id, kind.as_str());
+    if let Some(parent_id) = parent_id {
+        key.push_str(&format!("|{}", parent_id));
+    }
+    if let Some(filter) = filter {
+        key.push_str(&format!("|{}", serde_json::to_string(filter).unwrap_or_default()));
+    }
+    key
+}
+
+pub fn get<T: DeserializeOwned>(conn: &Connection, key: &str) -> Result<Option<CacheEntry<T>>> {
+    let row = conn
+        .query_row(
+            "SELECT items, etag, strftime('%s','now') - fetched_at FROM gitlab_cache WHERE cache_key=?1",
+            params![key],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, i64>(2)?)),
+        )
+        .optional()?;
+
+    // conteúdo ilegível é tratado como ausência de cache
+    Ok(row.and_then(|(items, etag, age)| {
+        serde_json::from_str(&items)
+            .ok()
+            .map(|items| CacheEntry { items, etag, age })
+    }))
+}
+
+pub fn put<T: Serialize>(
+    conn: &Connection,
+    key: &str,
+    kind: ListKind,
+    parent_id: Option<i64>,
+    items: &[T],
+    etag: Option<&str>,
+) -> Result<()> {
+    let items = serde_json::to_string(items).unwrap_or_else(|_| "[]".into());
+    conn.execute(
+        "INSERT INTO gitlab_cache (cache_key, kind, parent_id, items, etag, fetched_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, strftime('%s','now'))
+         ON CONFLICT(cache_key) DO UPDATE SET
+            items=excluded.items, etag=excluded.etag, fetched_at=excluded.fetched_at",
+        params![key, kind.as_str(), parent_id, items, etag],
+    )?;
+    Ok(())
+}
+
+/// Marca a listagem como atual sem mudar o conteúdo (resposta 304)
+pub fn touch(conn: &Connection, key: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE gitlab_cache SET fetched_at=strftime('%s','now') WHERE cache_key=?1",
+        params![key],
+    )?;
+    Ok(())
+}
+
+/// Apaga todo o cache de grupos, projetos e issues
+pub fn purge(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM gitlab_cache", [])?;
+    Ok(())
+}
+
+/// Apaga o cache de um perfil
+pub fn purge_profile(conn: &Connection, profile_id: i64) -> Result<()> {
+    conn.execute(
+        "DELETE FROM gitlab_cache WHERE cache_key LIKE ?1 || '|%'",
+        params![profile_id],
+    )?;
+    Ok(())
+}
+
+/// Busca a listagem completa com `fetch` (até o limite do perfil) e, com `store`, grava no cache
+pub async fn fetch_list<T, F, Fut>(
+    conn: &Mutex<Connection>,
+    profile_id: i64,
+    key: &str,
+    kind: ListKind,
+    parent_id: Option<i64>,
+    store: bool,
+    fetch: F,
+) -> Result<Vec<T>>
+where
+    T: Serialize,
+    F: FnOnce(usize) -> Fut,
+    Fut: Future<Output = Result<Fetched<T>>>,
+{
+    let limit = {
+        let conn = conn.lock()?;
+        config::page_limit(&conn, profile_id)?
+    };
+    let (items, etag) = match fetch(limit).await? {
+        Fetched::Items(items, etag) => (items, etag),
+        Fetched::NotModified => (Vec::new(), None),
+    };
+    if store {
+        let conn = conn.lock()?;
+        put(&conn, key, kind, parent_id, &items, etag.as_deref())?;
+    }
+    Ok(items)
+}
+
+/// Revalida a listagem em cache; `fetch` deve mandar o ETag guardado.
+///
+/// Devolve `true` se o conteúdo mudou.
+pub async fn refresh_list<T, F, Fut>(
+    conn: &Mutex<Connection>,
+    profile_id: i64,
+    key: &str,
+    kind: ListKind,
+    parent_id: Option<i64>,
+    fetch: F,
+) -> Result<bool>
+where
+    T: Serialize,
+    F: FnOnce(usize) -> Fut,
+    Fut: Future<Output = Result<Fetched<T>>>,
+{
+    let limit = {
+        let conn = conn.lock()?;
+        config::page_limit(&conn, profile_id)?
+    };
+    let fetched = fetch(limit).await?;
+    let conn = conn.lock()?;
+    match fetched {
+        Fetched::NotModified => {
+            touch(&conn, key)?;
+            Ok(false)
+        }
+        Fetched::Items(items, etag) => {
+            put(&conn, key, kind, parent_id, &items, etag.as_deref())?;
+            Ok(true)
+        }
+    }
+}
@@ -0,0 +1,561 @@
+use crate::backend::BackendKind;
+use crate::cache;
+use crate::error::{Error, Result};
+use crate::http::HttpSettings;
+use crate::oauth::OAuthTokens;
+use crate::secrets::{self, SecretStore};
+use crate::state::TimerSettings;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Limite padrão de itens por listagem (grupos, projetos, issues)
+const DEFAULT_PAGE_LIMIT: i64 = 1000;
+
+const PROFILE_COLUMNS: &str = "id, name, backend_kind, url, token, labels, gitlab_user, gitlab_user_id, page_limit, \
+     token_hint, auth_kind, oauth_client_id, token_expires_at";
+
+/// Como o perfil se autentica (coluna `profiles.auth_kind`)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthKind {
+    /// Personal access token colado pelo usuário
+    #[default]
+    Token,
+    /// Login no navegador; o segredo guardado é o JSON de `OAuthTokens`
+    OAuth,
+}
+
+impl AuthKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuthKind::Token => "token",
+            AuthKind::OAuth => "oauth",
+        }
+    }
+}
+
+/// Conta em uma instância de rastreador (tabela `profiles`).
+///
+/// Cada sessão guarda o perfil em que foi criada, para o tempo ir para a instância certa.
+/// O token fica no `SecretStore`; para a interface só vão `token_hint` e `has_token`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Profile {
+    /// 0 para um perfil ainda não salvo
+    pub id: i64,
+    pub name: String,
+    pub backend_kind: BackendKind,
+    pub url: String,
+    pub auth_kind: AuthKind,
+    /// Application ID cadastrado no GitLab, usado no login OAuth
+    pub oauth_client_id: String,
+    /// Token novo vindo da interface; vazio mantém o guardado. Nunca é devolvido.
+    #[serde(skip_serializing)]
+    pub token: String,
+    #[serde(skip_deserializing)]
+    pub token_hint: String,
+    #[serde(skip_deserializing)]
+    pub has_token: bool,
+    /// Validade do token (`AAAA-MM-DD`) informada no último teste de conexão
+    pub token_expires_at: Option<String>,
+    pub labels: String,
+    pub gitlab_user: String,
+    pub gitlab_user_id: i64,
+    /// `None` mantém o limite já salvo
+    pub page_limit: Option<i64>,
+}
+
+impl Profile {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let token_hint = row.get::<_, Option<String>>(9)?.unwrap_or_default();
+        Ok(Profile {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            backend_kind: row
+                .get::<_, Option<String>>(2)?
+                .and_then(|kind| kind.parse().ok())
+                .unwrap_or_default(),
+            url: row.get(3)?,
+            auth_kind: match row.get::<_, Option<String>>(10)?.as_deref() {
+                Some("oauth") => AuthKind::OAuth,
+                _ => AuthKind::Token,
+            },
+            oauth_client_id: row.get::<_, Option<String>>(11)?.unwrap_or_default(),
+            token_expires_at: row.get(12)?,
+            // só tokens ainda não migrados para o `SecretStore`
+            token: row.get(4)?,
+            has_token: !token_hint.is_empty(),
+            token_hint,
+            labels: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+            gitlab_user: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+            gitlab_user_id: row.get::<_, Option<i64>>(7)?.unwrap_or(0),
+            page_limit: row.get(8)?,
+        })
+    }
+}
+
+/// Cria (`id` 0) ou atualiza o perfil e limpa o cache dele; devolve o id.
+///
+/// Um token informado vai para `store`; no banco fica só a forma mascarada.
+pub fn save(conn: &Connection, store: &dyn SecretStore, profile: &Profile) -> Result<i64> {
+    let name = if profile.name.trim().is_empty() { &profile.url } else { &profile.name };
+    let token = profile.token.trim();
+    if profile.id == 0 {
+        if token.is_empty() {
+            return Err(Error::InvalidState("Informe o token de acesso".into()));
+        }
+        conn.execute(
+            "INSERT INTO profiles (name, backend_kind, url, token, token_hint, labels, gitlab_user, gitlab_user_id,
+                                   page_limit, auth_kind, oauth_client_id, token_expires_at)
+             VALUES (?1, ?2, ?3, '', ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                name,
+                profile.backend_kind.as_str(),
+                profile.url,
+                token_hint(profile.auth_kind, token),
+                profile.labels,
+                profile.gitlab_user,
+                profile.gitlab_user_id,
+                profile.page_limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+                profile.auth_kind.as_str(),
+                profile.oauth_client_id,
+                profile.token_expires_at
+            ],
+        )?;
+        let id = conn.last_insert_rowid();
+        if let Err(e) = store.set(&secrets::account(id), token) {
+            conn.execute("DELETE FROM profiles WHERE id=?1", params![id])?;
+            return Err(e);
+        }
+        return Ok(id);
+    }
+
+    let updated = conn.execute(
+        "UPDATE profiles
+         SET name=?2, backend_kind=?3, url=?4, labels=?5, gitlab_user=?6, gitlab_user_id=?7,
+             page_limit=COALESCE(?8, page_limit), auth_kind=?9, oauth_client_id=?10, token_expires_at=?11
+         WHERE id=?1",
+        params![
+            profile.id,
+            name,
+            profile.backend_kind.as_str(),
+            profile.url,
+            profile.labels,
+            profile.gitlab_user,
+            profile.gitlab_user_id,
+            profile.page_limit,
+            profile.auth_kind.as_str(),
+            profile.oauth_client_id,
+            profile.token_expires_at
+        ],
+    )?;
+    if updated == 0 {
+        return Err(not_found(profile.id));
+    }
+    if !token.is_empty() {
+        seal_token(conn, store, profile.id, token, &token_hint(profile.auth_kind, token))?;
+    }
+    // a URL ou o usuário podem ter mudado
+    cache::purge_profile(conn, profile.id)?;
+    Ok(profile.id)
+}
+
+/// Token do perfil, do `store` (ou do banco, se ainda não foi migrado)
+pub fn token(conn: &Connection, store: &dyn SecretStore, profile_id: i64) -> Result<String> {
+    if let Some(token) = store.get(&secrets::account(profile_id))? {
+        return Ok(token);
+    }
+    let token = load(conn, profile_id)?.token;
+    if token.is_empty() {
+        return Err(Error::NotConfigured);
+    }
+    Ok(token)
+}
+
+/// Move para `store` os tokens que ainda estão em texto puro no banco; devolve quantos.
+///
+/// Liga o `secure_delete` da conexão, para os tokens apagados daqui em diante serem
+/// zerados no arquivo, e reescreve o banco com `VACUUM` quando sela algum (e uma vez
+/// para os que a migração v9 e versões anteriores do app já tinham apagado sem isso).
+pub fn seal_tokens(conn: &Connection, store: &dyn SecretStore) -> Result<usize> {
+    conn.pragma_update(None, "secure_delete", true)?;
+    let plain: Vec<(i64, String)> = {
+        let mut stmt = conn.prepare("SELECT id, token FROM profiles WHERE token <> ''")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        rows
+    };
+    for (profile_id, token) in &plain {
+        seal_token(conn, store, *profile_id, token, &secrets::mask(token))?;
+    }
+    if !plain.is_empty() || !load_setting::<bool>(conn, "tokens_vacuumed")? {
+        conn.execute_batch("VACUUM")?;
+        save_setting(conn, "tokens_vacuumed", &true)?;
+    }
+    Ok(plain.len())
+}
+
+/// Apaga os tokens em texto puro das cópias `config.db.v{N}.bak` em `data_dir`.
+///
+/// As cópias são tiradas antes de migrar, quando os tokens ainda ficavam no banco;
+/// chamada depois de `seal_tokens`, para que nenhum fique legível ao lado dele.
+pub fn scrub_backups(data_dir: &Path) -> Result<usize> {
+    let mut scrubbed = 0;
+    for entry in std::fs::read_dir(data_dir).map_err(|e| Error::Internal(format!("pasta de dados: {}", e)))? {
+        let Ok(entry) = entry else { continue };
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with("config.db.v") && name.ends_with(".bak") {
+            scrub_backup(&entry.path())?;
+            scrubbed += 1;
+        }
+    }
+    Ok(scrubbed)
+}
+
+fn scrub_backup(path: &Path) -> Result<()> {
+    let conn = Connection::open(path)?;
+    for table in ["config", "profiles"] {
+        let has_token: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name='token'",
+            params![table],
+            |row| row.get(0),
+        )?;
+        if has_token {
+            conn.execute(&format!("UPDATE {} SET token='' WHERE token <> ''", table), [])?;
+        }
+    }
+    // reescreve o arquivo para não sobrar o texto antigo nas páginas livres
+    conn.execute_batch("VACUUM")?;
+    Ok(())
+}
+
+/// Guarda os tokens renovados de um perfil OAuth
+pub fn save_oauth_tokens(store: &dyn SecretStore, profile_id: i64, tokens: &OAuthTokens) -> Result<()> {
+    store.set(&secrets::account(profile_id), &tokens.to_secret()?)
+}
+
+fn seal_token(conn: &Connection, store: &dyn SecretStore, profile_id: i64, token: &str, hint: &str) -> Result<()> {
+    store.set(&secrets::account(profile_id), token)?;
+    conn.execute(
+        "UPDATE profiles SET token='', token_hint=?2 WHERE id=?1",
+        params![profile_id, hint],
+    )?;
+    Ok(())
+}
+
+/// O que a interface mostra no lugar do segredo
+fn token_hint(auth_kind: AuthKind, token: &str) -> String {
+    match auth_kind {
+        AuthKind::Token => secrets::mask(token),
+        AuthKind::OAuth => "OAuth".into(),
+    }
+}
+
+pub fn load(conn: &Connection, profile_id: i64) -> Result<Profile> {
+    conn.query_row(
+        &format!("SELECT {} FROM profiles WHERE id=?1", PROFILE_COLUMNS),
+        params![profile_id],
+        Profile::from_row,
+    )
+    .optional()?
+    .ok_or_else(|| not_found(profile_id))
+}
+
+/// Todos os perfis, na ordem em que foram criados
+pub fn list(conn: &Connection) -> Result<Vec<Profile>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM profiles ORDER BY id", PROFILE_COLUMNS))?;
+    let profiles = stmt.query_map([], Profile::from_row)?.collect::<rusqlite::Result<_>>()?;
+    Ok(profiles)
+}
+
+/// Remove o perfil e o token; recusa se ainda houver tempo dele esperando envio
+pub fn delete(conn: &Connection, store: &dyn SecretStore, profile_id: i64) -> Result<()> {
+    let pending: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sync_outbox o JOIN sessions s ON s.id = o.session_id WHERE s.profile_id=?1",
+        params![profile_id],
+        |row| row.get(0),
+    )?;
+    if pending > 0 {
+        return Err(Error::InvalidState(format!(
+            "O perfil tem {} lançamento(s) aguardando envio",
+            pending
+        )));
+    }
+    conn.execute("DELETE FROM profiles WHERE id=?1", params![profile_id])?;
+    store.delete(&secrets::account(profile_id))?;
+    cache::purge_profile(conn, profile_id)
+}
+
+/// Perfil cujo token expira em breve
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TokenExpiry {
+    pub profile_id: i64,
+    pub profile_name: String,
+    pub expires_at: String,
+    /// Dias até expirar (0 = expira hoje, negativo = já expirou)
+    pub days_left: i64,
+}
+
+/// Perfis com token expirando em até `within_days` dias a partir de `now` (unix)
+pub fn expiring_tokens(conn: &Connection, now: i64, within_days: i64) -> Result<Vec<TokenExpiry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, token_expires_at, days_left
+         FROM (SELECT id, name, token_expires_at,
+                      CAST(julianday(token_expires_at) - julianday(date(?1, 'unixepoch')) AS INTEGER) AS days_left
+               FROM profiles
+               WHERE token_expires_at IS NOT NULL AND auth_kind = 'token')
+         WHERE days_left <= ?2
+         ORDER BY days_left",
+    )?;
+    let expiring = stmt
+        .query_map(params![now, within_days], |row| {
+            Ok(TokenExpiry {
+                profile_id: row.get(0)?,
+                profile_name: row.get(1)?,
+                expires_at: row.get(2)?,
+                days_left: row.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(expiring)
+}
+
+/// Máximo de itens buscados em cada listagem do perfil
+pub fn page_limit(conn: &Connection, profile_id: i64) -> Result<usize> {
+    let limit: Option<i64> = conn
+        .query_row("SELECT page_limit FROM profiles WHERE id=?1", params![profile_id], |row| row.get(0))
+        .optional()?
+        .flatten();
+    Ok(limit.filter(|l| *l > 0).unwrap_or(DEFAULT_PAGE_LIMIT) as usize)
+}
+
+/// Configuração de rede (certificados, proxy, timeouts)
+pub fn http_settings(conn: &Connection) -> Result<HttpSettings> {
+    load_setting(conn, "http")
+}
+
+pub fn save_http_settings(conn: &Connection, settings: &HttpSettings) -> Result<()> {
+    save_setting(conn, "http", settings)
+}
+
+/// Preferências do timer (troca de tarefa etc.)
+pub fn timer_settings(conn: &Connection) -> Result<TimerSettings> {
+    load_setting(conn, "timer")
+}
+
+pub fn save_timer_settings(conn: &Connection, settings: &TimerSettings) -> Result<()> {
+    save_setting(conn, "timer", settings)
+}
+
+/// Preferência salva em `settings`, ou o padrão se ainda não houver
+fn load_setting<T: DeserializeOwned + Default>(conn: &Connection, key: &str) -> Result<T> {
+    let value: Option<String> = conn
+        .query_row("SELECT value FROM settings WHERE key=?1", params![key], |row| row.get(0))
+        .optional()?;
+    match value {
+        Some(json) => serde_json::from_str(&json).map_err(|e| Error::Internal(format!("preferência {}: {}", key, e))),
+        None => Ok(T::default()),
+    }
+}
+
+fn save_setting<T: Serialize>(conn: &Connection, key: &str, value: &T) -> Result<()> {
+    let json = serde_json::to_string(value).map_err(|e| Error::Internal(e.to_string()))?;
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value=excluded.value",
+        params![key, json],
+    )?;
+    Ok(())
+}
+
+fn not_found(profile_id: i64) -> Error {
+    Error::NotFound(format!("Perfil {} não encontrado", profile_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations;
+    use crate::secrets::MemoryStore;
+    use crate::state::SwitchAction;
+
+    fn db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut conn, None).unwrap();
+        conn
+    }
+
+    #[test]
+    fn keeps_profiles_apart() {
+        let conn = db();
+        let store = MemoryStore::default();
+        let work = save(&conn, &store, &Profile {
+            url: "https://gitlab.empresa.com".into(),
+            token: "glpat-empresa-0001".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        let public = save(&conn, &store, &Profile {
+            name: "gitlab.com".into(),
+            url: "https://gitlab.com".into(),
+            token: "glpat-publico-0002".into(),
+            page_limit: Some(200),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let profiles = list(&conn).unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].name, "https://gitlab.empresa.com");
+        assert_eq!(page_limit(&conn, work).unwrap(), 1000);
+        assert_eq!(page_limit(&conn, public).unwrap(), 200);
+        assert_eq!(token(&conn, &store, public).unwrap(), "glpat-publico-0002");
+
+        delete(&conn, &store, work).unwrap();
+        assert!(matches!(load(&conn, work), Err(Error::NotFound(_))));
+        assert_eq!(store.get(&secrets::account(work)).unwrap(), None);
+        assert_eq!(load(&conn, public).unwrap().url, "https://gitlab.com");
+    }
+
+    #[test]
+    fn token_never_stays_in_the_database() {
+        let conn = db();
+        let store = MemoryStore::default();
+        let id = save(&conn, &store, &Profile {
+            url: "https://gitlab.com".into(),
+            token: "glpat-abcdefghij1234".into(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let profile = load(&conn, id).unwrap();
+        assert_eq!((profile.token.as_str(), profile.token_hint.as_str()), ("", "glpat-••••1234"));
+        assert!(profile.has_token);
+        let json = serde_json::to_string(&profile).unwrap();
+        assert!(!json.contains("abcdefghij"), "{}", json);
+
+        // salvar sem token mantém o guardado
+        save(&conn, &store, &Profile { name: "outro nome".into(), ..profile }).unwrap();
+        assert_eq!(token(&conn, &store, id).unwrap(), "glpat-abcdefghij1234");
+    }
+
+    #[test]
+    fn lists_tokens_about_to_expire() {
+        let conn = db();
+        let store = MemoryStore::default();
+        for (url, expires_at) in [("https://a", Some("2026-03-10")), ("https://b", Some("2026-04-30")), ("https://c", None)] {
+            save(&conn, &store, &Profile {
+                url: url.into(),
+                token: "glpat-0000000000".into(),
+                token_expires_at: expires_at.map(str::to_string),
+                ..Default::default()
+            })
+            .unwrap();
+        }
+        // 2026-03-03 12:00 UTC
+        let now = 1_772_539_200;
+
+        let expiring = expiring_tokens(&conn, now, 7).unwrap();
+
+        assert_eq!(expiring, vec![TokenExpiry {
+            profile_id: 1,
+            profile_name: "https://a".into(),
+            expires_at: "2026-03-10".into(),
+            days_left: 7,
+        }]);
+        assert_eq!(expiring_tokens(&conn, now + 30 * 86_400, 7).unwrap()[0].days_left, -23);
+    }
+
+    #[test]
+    fn http_settings_round_trip() {
+        let conn = db();
+        assert_eq!(http_settings(&conn).unwrap(), HttpSettings::default());
+
+        let settings = HttpSettings {
+            ca_certs: vec!["/etc/ssl/empresa.pem".into()],
+            proxy_url: Some("http://proxy.empresa:3128".into()),
+            no_proxy: "localhost".into(),
+            timeout_secs: Some(60),
+            ..Default::default()
+        };
+        save_http_settings(&conn, &settings).unwrap();
+        save_http_settings(&conn, &settings).unwrap();
+
+        assert_eq!(http_settings(&conn).unwrap(), settings);
+    }
+
+    #[test]
+    fn timer_settings_default_to_stopping_on_switch() {
+        let conn = db();
+        assert_eq!(timer_settings(&conn).unwrap().switch_action, SwitchAction::Stop);
+
+        let settings = TimerSettings { switch_action: SwitchAction::Pause, idle_minutes: 0, auto_resume: true };
+        save_timer_settings(&conn, &settings).unwrap();
+
+        assert_eq!(timer_settings(&conn).unwrap().switch_action, SwitchAction::Pause);
+    }
+
+    #[test]
+    fn seals_plaintext_tokens() {
+        let conn = db();
+        conn.execute(
+            "INSERT INTO profiles (name, url, token) VALUES ('antigo', 'https://gitlab.com', 'glpat-texto-puro-99')",
+            [],
+        )
+        .unwrap();
+        let store = MemoryStore::default();
+
+        assert_eq!(seal_tokens(&conn, &store).unwrap(), 1);
+
+        let plain: String = conn.query_row("SELECT token FROM profiles", [], |row| row.get(0)).unwrap();
+        assert_eq!(plain, "");
+        assert_eq!(token(&conn, &store, 1).unwrap(), "glpat-texto-puro-99");
+        assert_eq!(seal_tokens(&conn, &store).unwrap(), 0);
+    }
+
+    #[test]
+    fn sealed_tokens_leave_no_trace_in_the_file() {
+        let path = std::env::temp_dir().join(format!("ttd-seal-{}.db", std::process::id()));
+        let mut conn = Connection::open(&path).unwrap();
+        migrations::migrate(&mut conn, None).unwrap();
+        conn.execute(
+            "INSERT INTO profiles (name, url, token) VALUES ('antigo', 'https://gitlab.com', 'glpat-no-arquivo')",
+            [],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO profiles (name, url, token) VALUES ('outro', 'https://gitea.com', '')", []).unwrap();
+
+        seal_tokens(&conn, &MemoryStore::default()).unwrap();
+
+        drop(conn);
+        let raw = std::fs::read(&path).unwrap();
+        assert!(!raw.windows(16).any(|w| w == b"glpat-no-arquivo"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn backups_lose_their_plaintext_tokens() {
+        let dir = std::env::temp_dir().join(format!("ttd-backups-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let backup_path = dir.join("config.db.v9.bak");
+        let conn = db();
+        conn.execute(
+            "INSERT INTO profiles (name, url, token) VALUES ('work', 'https://gitlab.com', 'glpat-secreto')",
+            [],
+        )
+        .unwrap();
+        conn.execute("VACUUM INTO ?1", params![backup_path.to_string_lossy()]).unwrap();
+
+        assert_eq!(scrub_backups(&dir).unwrap(), 1);
+
+        let raw = std::fs::read(&backup_path).unwrap();
+        assert!(!raw.windows(13).any(|w| w == b"glpat-secreto"));
+        let backup = Connection::open(&backup_path).unwrap();
+        let token: String = backup.query_row("SELECT token FROM profiles", [], |row| row.get(0)).unwrap();
+        assert_eq!(token, "");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+use crate::error::{Error, Result};
+use crate::models::IssueRef;
+use crate::sync;
+use crate::tracker::{Clock, Tracker};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Início e fim (segundos Unix) de um intervalo informado pelo usuário
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+/// Intervalo gravado de uma sessão; sem `end_time` é o que está correndo
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Interval {
+    pub id: i64,
+    pub session_id: i64,
+    pub start_time: i64,
+    pub end_time: Option<i64>,
+    pub recovered: bool,
+    /// Tempo ausente ainda sem decisão (manter, descartar ou passar para outra issue)
+    pub idle: bool,
+}
+
+/// Perfil, projeto, issue e status de uma sessão, para conferir antes de juntar
+struct SessionKey {
+    profile_id: Option<i64>,
+    project_id: Option<i64>,
+    issue_id: Option<i64>,
+    status: String,
+}
+
+impl<C: Clock> Tracker<'_, C> {
+    /// Lança uma sessão já finalizada com os intervalos informados (timer esquecido)
+    pub fn create_manual(
+        &self,
+        profile_id: i64,
+        group_id: i64,
+        project_id: i64,
+        issue: &IssueRef,
+        label: &str,
+        spans: &[Span],
+    ) -> Result<i64> {
+        if spans.is_empty() {
+            return Err(Error::InvalidState("Informe ao menos um intervalo".into()));
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "INSERT INTO sessions (profile_id, group_id, project_id, issue_id, issue_iid, issue_title, issue_web_url, label, status, manual, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'finalizado', 1, ?9)",
+            params![profile_id, group_id, project_id, issue.id, issue.iid, issue.title, issue.web_url, label, self.now()],
+        )?;
+        let session_id = self.conn.last_insert_rowid();
+        // cada intervalo é conferido contra os já gravados, inclusive os anteriores da lista
+        for span in spans {
+            self.check_span(span, None)?;
+            self.insert_interval(session_id, span)?;
+        }
+        sync::enqueue_session(self.conn, session_id)?;
+        tx.commit()?;
+        Ok(session_id)
+    }
+
+    /// Intervalos da sessão em ordem cronológica
+    pub fn intervals(&self, session_id: i64) -> Result<Vec<Interval>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, session_id, start_time, end_time, COALESCE(recovered, 0), idle
+             FROM session_intervals WHERE session_id=?1 ORDER BY start_time",
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| {
+            Ok(Interval {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                start_time: row.get(2)?,
+                end_time: row.get(3)?,
+                recovered: row.get(4)?,
+                idle: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Corrige início e fim de um intervalo fechado
+    pub fn update_interval(&self, interval_id: i64, span: &Span) -> Result<()> {
+        let session_id = self.closed_interval_session(interval_id)?;
+        self.check_not_sending(session_id)?;
+        self.check_span(span, Some(interval_id))?;
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "UPDATE session_intervals SET start_time=?2, end_time=?3, heartbeat_at=?3 WHERE id=?1",
+            params![interval_id, span.start_time, span.end_time],
+        )?;
+        self.touch(session_id)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Remove um intervalo fechado; a sessão que fica vazia e nunca foi enviada some junto
+    pub fn delete_interval(&self, interval_id: i64) -> Result<()> {
+        let session_id = self.closed_interval_session(interval_id)?;
+        self.check_not_sending(session_id)?;
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute("DELETE FROM session_intervals WHERE id=?1", params![interval_id])?;
+
+        let (remaining, synced_seconds): (i64, i64) = self.conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM session_intervals WHERE session_id=?1), synced_seconds
+             FROM sessions WHERE id=?1",
+            params![session_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if remaining == 0 && synced_seconds == 0 {
+            self.delete_session(session_id)?;
+        } else {
+            self.touch(session_id)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Divide a sessão em `at`: o que vem depois vai para uma sessão nova na mesma issue.
+    ///
+    /// O intervalo que contém `at` é cortado em dois. A sessão nova já nasce finalizada,
+    /// mesmo quando a original está pausada. Retorna o id da sessão nova.
+    pub fn split_session(&self, session_id: i64, at: i64) -> Result<i64> {
+        let key = self.session_key(session_id)?;
+        if key.status == "runner" {
+            return Err(Error::InvalidState("Pause o timer antes de dividir a sessão".into()));
+        }
+        self.check_not_sending(session_id)?;
+        let (first, last): (Option<i64>, Option<i64>) = self.conn.query_row(
+            "SELECT MIN(start_time), MAX(end_time) FROM session_intervals WHERE session_id=?1",
+            params![session_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if !matches!((first, last), (Some(first), Some(last)) if first < at && at < last) {
+            return Err(Error::InvalidState("O ponto de divisão precisa estar dentro da sessão".into()));
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "INSERT INTO sessions (profile_id, group_id, project_id, issue_id, issue_iid, issue_title, issue_web_url, label, status, manual, updated_at)
+             SELECT profile_id, group_id, project_id, issue_id, issue_iid, issue_title, issue_web_url, label, 'finalizado', manual, ?2
+             FROM sessions WHERE id=?1",
+            params![session_id, self.now()],
+        )?;
+        let new_id = self.conn.last_insert_rowid();
+        self.conn.execute(
+            "INSERT INTO session_intervals (session_id, start_time, end_time, heartbeat_at, recovered, idle)
+             SELECT ?2, ?3, end_time, end_time, recovered, idle
+             FROM session_intervals WHERE session_id=?1 AND start_time < ?3 AND end_time > ?3",
+            params![session_id, new_id, at],
+        )?;
+        self.conn.execute(
+            "UPDATE session_intervals SET end_time=?2, heartbeat_at=?2
+             WHERE session_id=?1 AND start_time < ?2 AND end_time > ?2",
+            params![session_id, at],
+        )?;
+        self.conn.execute(
+            "UPDATE session_intervals SET session_id=?2 WHERE session_id=?1 AND start_time >= ?3",
+            params![session_id, new_id, at],
+        )?;
+        self.touch(session_id)?;
+        self.touch(new_id)?;
+        tx.commit()?;
+        Ok(new_id)
+    }
+
+    /// Junta os intervalos de `source` em `target`; as duas precisam ser da mesma issue.
+    ///
+    /// O tempo já enviado de `source` passa para `target`, então só a diferença vai ao rastreador.
+    pub fn merge_sessions(&self, target_id: i64, source_id: i64) -> Result<()> {
+        if target_id == source_id {
+            return Err(Error::InvalidState("Escolha duas sessões diferentes".into()));
+        }
+        let target = self.session_key(target_id)?;
+        let source = self.session_key(source_id)?;
+        if target.status == "runner" || source.status == "runner" {
+            return Err(Error::InvalidState("Pause o timer antes de juntar sessões".into()));
+        }
+        if (target.profile_id, target.project_id, target.issue_id)
+            != (source.profile_id, source.project_id, source.issue_id)
+        {
+            return Err(Error::InvalidState("Só é possível juntar sessões da mesma issue".into()));
+        }
+        self.check_not_sending(target_id)?;
+        self.check_not_sending(source_id)?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "UPDATE session_intervals SET session_id=?1 WHERE session_id=?2",
+            params![target_id, source_id],
+        )?;
+        self.conn.execute(
+            "UPDATE sessions
+             SET synced_seconds = synced_seconds + (SELECT synced_seconds FROM sessions WHERE id=?2)
+             WHERE id=?1",
+            params![target_id, source_id],
+        )?;
+        self.delete_session(source_id)?;
+        self.touch(target_id)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Recusa intervalos invertidos, no futuro ou sobrepostos a outro (de qualquer sessão)
+    fn check_span(&self, span: &Span, ignore: Option<i64>) -> Result<()> {
+        if span.end_time <= span.start_time {
+            return Err(Error::InvalidState("O fim do intervalo precisa ser depois do início".into()));
+        }
+        let now = self.now();
+        if span.end_time > now {
+            return Err(Error::InvalidState("O intervalo não pode terminar no futuro".into()));
+        }
+        let overlapping: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT session_id FROM session_intervals
+                 WHERE id IS NOT ?1 AND start_time < ?3 AND COALESCE(end_time, ?4) > ?2
+                 LIMIT 1",
+                params![ignore, span.start_time, span.end_time, now],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match overlapping {
+            Some(session_id) => Err(Error::InvalidState(format!(
+                "O intervalo se sobrepõe a outro da sessão {}",
+                session_id
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Recusa mexer na sessão enquanto o lançamento dela está em voo: o tempo enviado
+    /// seria creditado a uma sessão que não existe mais e mandado de novo
+    pub(crate) fn check_not_sending(&self, session_id: i64) -> Result<()> {
+        if sync::is_sending(self.conn, session_id)? {
+            return Err(Error::InvalidState(format!(
+                "A sessão {} está sendo enviada; tente de novo em instantes",
+                session_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Sessão do intervalo; o intervalo que está correndo não pode ser editado
+    fn closed_interval_session(&self, interval_id: i64) -> Result<i64> {
+        let (session_id, end_time): (i64, Option<i64>) = self
+            .conn
+            .query_row(
+                "SELECT session_id, end_time FROM session_intervals WHERE id=?1",
+                params![interval_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .ok_or_else(|| Error::NotFound(format!("Intervalo {} não encontrado", interval_id)))?;
+        if end_time.is_none() {
+            return Err(Error::InvalidState("Pause o timer antes de editar o intervalo em andamento".into()));
+        }
+        Ok(session_id)
+    }
+
+    fn session_key(&self, session_id: i64) -> Result<SessionKey> {
+        self.conn
+            .query_row(
+                "SELECT profile_id, project_id, issue_id, COALESCE(status, '') FROM sessions WHERE id=?1",
+                params![session_id],
+                |row| {
+                    Ok(SessionKey {
+                        profile_id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        issue_id: row.get(2)?,
+                        status: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?
+            .ok_or_else(|| Error::NotFound(format!("Sessão {} não encontrada", session_id)))
+    }
+
+    fn insert_interval(&self, session_id: i64, span: &Span) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO session_intervals (session_id, start_time, end_time, heartbeat_at)
+             VALUES (?1, ?2, ?3, ?3)",
+            params![session_id, span.start_time, span.end_time],
+        )?;
+        Ok(())
+    }
+
+    /// Marca a sessão como alterada e atualiza o lançamento na fila de envio
+    fn touch(&self, session_id: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET updated_at=?2 WHERE id=?1",
+            params![session_id, self.now()],
+        )?;
+        sync::enqueue_session(self.conn, session_id)
+    }
+
+    fn delete_session(&self, session_id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM sync_outbox WHERE session_id=?1", params![session_id])?;
+        self.conn.execute("DELETE FROM session_intervals WHERE session_id=?1", params![session_id])?;
+        self.conn.execute("DELETE FROM sessions WHERE id=?1", params![session_id])?;
+        Ok(())
+    }
+}
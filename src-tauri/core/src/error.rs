@@ -0,0 +1,115 @@
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::sync::PoisonError;
+
+/// Erro devolvido por todos os comandos.
+///
+/// Chega ao frontend como `{ kind, message }` (mais `status` e `body` em `gitlab`),
+/// para a interface poder decidir o que mostrar a partir de `kind`.
+#[derive(Debug)]
+pub enum Error {
+    /// Falha no SQLite
+    Database(String),
+    /// Servidor inacessível (DNS, conexão, timeout)
+    Network(String),
+    /// Token inválido, expirado ou sem permissão (401/403)
+    Auth(String),
+    /// Nenhuma configuração de rastreador salva
+    NotConfigured,
+    /// Resposta de erro da API do rastreador (o `kind` continua `gitlab`)
+    GitLab { status: u16, body: String },
+    /// Operação inválida para o estado atual (ex.: iniciar com timer rodando)
+    InvalidState(String),
+    /// Registro não encontrado
+    NotFound(String),
+    /// Erro interno (lock envenenado, resposta inesperada)
+    Internal(String),
+    /// Falha depois de `sent` segundos do lançamento já terem chegado ao rastreador
+    Partial { sent: i64, source: Box<Error> },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Marca o erro como parcial quando parte do lançamento (`sent` segundos) já foi feita
+    pub fn after_sent(self, sent: i64) -> Error {
+        if sent == 0 {
+            self
+        } else {
+            Error::Partial { sent, source: Box::new(self) }
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Database(_) => "database",
+            Error::Network(_) => "network",
+            Error::Auth(_) => "auth",
+            Error::NotConfigured => "not_configured",
+            Error::GitLab { .. } => "gitlab",
+            Error::InvalidState(_) => "invalid_state",
+            Error::NotFound(_) => "not_found",
+            Error::Internal(_) => "internal",
+            Error::Partial { source, .. } => source.kind(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Database(e) => write!(f, "Erro no banco de dados: {}", e),
+            Error::Network(e) => write!(f, "Erro de conexão com o servidor: {}", e),
+            Error::Auth(e) => write!(f, "Acesso negado pelo servidor: {}", e),
+            Error::NotConfigured => write!(f, "Configuração não encontrada"),
+            Error::GitLab { status, body } => write!(f, "Erro da API ({}): {}", status, body),
+            Error::InvalidState(e) => write!(f, "{}", e),
+            Error::NotFound(e) => write!(f, "{}", e),
+            Error::Internal(e) => write!(f, "Erro interno: {}", e),
+            Error::Partial { source, .. } => write!(f, "{}", source),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if let Error::Partial { source, .. } = self {
+            return source.serialize(serializer);
+        }
+        let fields = if let Error::GitLab { .. } = self { 4 } else { 2 };
+        let mut s = serializer.serialize_struct("Error", fields)?;
+        s.serialize_field("kind", self.kind())?;
+        s.serialize_field("message", &self.to_string())?;
+        if let Error::GitLab { status, body } = self {
+            s.serialize_field("status", status)?;
+            s.serialize_field("body", body)?;
+        }
+        s.end()
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Database(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            Error::Internal(format!("resposta inesperada do servidor: {}", e))
+        } else {
+            Error::Network(e.to_string())
+        }
+    }
+}
+
+impl<T> From<PoisonError<T>> for Error {
+    fn from(e: PoisonError<T>) -> Self {
+        Error::Internal(e.to_string())
+    }
+}
@@ -0,0 +1,457 @@
+//! Cliente da API v1 do Gitea (também serve para Forgejo).
+//!
+//! Organizações fazem o papel dos grupos do GitLab e repositórios o dos projetos;
+//! as respostas são convertidas para os modelos do app em `crate::models`.
+
+use crate::backend::{Fetched, TrackerBackend};
+use crate::error::{Error, Result};
+use crate::http::{check, next_page};
+use crate::models::{Group, Issue, IssueFilter, Milestone, Note, Project, TimeStats, TokenInfo, User};
+use crate::tracker::format_gitlab_time;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Method, RequestBuilder, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Itens por página (limite padrão de `MAX_RESPONSE_ITEMS` do Gitea)
+const PAGE_SIZE: &str = "50";
+
+/// Acesso à API v1 de uma instância do Gitea
+pub struct GiteaClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl GiteaClient {
+    pub fn new(http: &reqwest::Client, base_url: &str, token: &str) -> Self {
+        GiteaClient {
+            http: http.clone(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    fn url(&self, path: &str) -> Result<Url> {
+        Url::parse(&format!("{}/api/v1{}", self.base_url, path))
+            .map_err(|e| Error::InvalidState(format!("URL do Gitea inválida: {}", e)))
+    }
+
+    fn list_url(&self, path: &str) -> Result<Url> {
+        let mut url = self.url(path)?;
+        url.query_pairs_mut().append_pair("limit", PAGE_SIZE);
+        Ok(url)
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        self.http
+            .request(method, url)
+            .header(AUTHORIZATION, format!("token {}", self.token))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: Url) -> Result<T> {
+        let resp = self.request(Method::GET, url).send().await?;
+        Ok(check(resp).await?.json().await?)
+    }
+
+    /// Busca todas as páginas (seguindo `Link`) até `limit` itens.
+    ///
+    /// `R` é o corpo de cada página: uma lista ou o envelope `{ data: [...] }` das buscas.
+    async fn list<R, T>(&self, url: &Url, limit: usize) -> Result<Vec<T>>
+    where
+        R: DeserializeOwned + Into<Vec<T>>,
+    {
+        let mut items = Vec::new();
+        let mut next = Some(url.clone());
+        while let Some(page_url) = next.take() {
+            let resp = check(self.request(Method::GET, page_url.clone()).send().await?).await?;
+            next = next_page(resp.headers(), &page_url);
+
+            let page: Vec<T> = resp.json::<R>().await?.into();
+            if page.is_empty() {
+                break;
+            }
+            items.extend(page);
+            if items.len() >= limit {
+                break;
+            }
+        }
+        items.truncate(limit);
+        Ok(items)
+    }
+
+    /// Repositório pelo id (as rotas de issues usam `dono/nome`)
+    async fn repo(&self, id: i64) -> Result<Repository> {
+        self.get(self.url(&format!("/repositories/{}", id))?).await
+    }
+
+    async fn issue_path(&self, project_id: i64, issue_iid: i64) -> Result<String> {
+        let repo = self.repo(project_id).await?;
+        Ok(format!("/repos/{}/issues/{}", repo.full_name, issue_iid))
+    }
+
+    async fn list_issues(&self, project_id: i64, filter: &IssueFilter, limit: usize) -> Result<Vec<Issue>> {
+        let repo = self.repo(project_id).await?;
+        let mut url = self.list_url(&format!("/repos/{}/issues", repo.full_name))?;
+        let login = if filter.assigned_to_me {
+            Some(self.gitea_user().await?.login)
+        } else {
+            None
+        };
+        apply_filter(filter, &mut url, login.as_deref());
+
+        let issues = self.list::<Vec<GiteaIssue>, GiteaIssue>(&url, limit).await?;
+        Ok(issues.into_iter().map(|issue| issue.into_issue(project_id)).collect())
+    }
+
+    async fn add_time(&self, issue_path: &str, seconds: i64) -> Result<()> {
+        let resp = self
+            .request(Method::POST, self.url(&format!("{}/times", issue_path))?)
+            .json(&json!({ "time": seconds }))
+            .send()
+            .await?;
+        check(resp).await?;
+        Ok(())
+    }
+
+    /// Apaga os lançamentos mais recentes do usuário até cobrir `seconds` e relança a sobra.
+    ///
+    /// Recusa quando o tempo próprio na issue não cobre o desconto. Uma falha no meio
+    /// volta como `Error::Partial` com o que já foi descontado, para a nova tentativa
+    /// seguir só com o restante.
+    async fn remove_time(&self, issue_path: &str, seconds: i64) -> Result<()> {
+        let login = self.gitea_user().await?.login;
+        let mut url = self.list_url(&format!("{}/times", issue_path))?;
+        url.query_pairs_mut().append_pair("user", &login);
+        let mut mine = self.list::<Vec<TrackedTime>, TrackedTime>(&url, usize::MAX).await?;
+        mine.sort_by_key(|time| std::cmp::Reverse(time.id));
+
+        let own: i64 = mine.iter().map(|time| time.time).sum();
+        if own < seconds {
+            return Err(Error::InvalidState(format!(
+                "Não dá para descontar {} da issue: {} só tem {} lançado nela",
+                format_gitlab_time(seconds),
+                login,
+                format_gitlab_time(own)
+            )));
+        }
+
+        let mut removed = 0;
+        for time in &mine {
+            if removed >= seconds {
+                break;
+            }
+            self.delete_time(issue_path, time.id).await.map_err(|e| e.after_sent(-removed))?;
+            removed += time.time;
+        }
+        if removed > seconds {
+            self.add_time(issue_path, removed - seconds)
+                .await
+                .map_err(|e| e.after_sent(-removed))?;
+        }
+        Ok(())
+    }
+
+    async fn delete_time(&self, issue_path: &str, id: i64) -> Result<()> {
+        let resp = self
+            .request(Method::DELETE, self.url(&format!("{}/times/{}", issue_path, id))?)
+            .send()
+            .await?;
+        check(resp).await?;
+        Ok(())
+    }
+
+    async fn gitea_user(&self) -> Result<GiteaUser> {
+        self.get(self.url("/user")?).await
+    }
+}
+
+impl TrackerBackend for GiteaClient {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// `GET /user`
+    async fn current_user(&self) -> Result<User> {
+        Ok(self.gitea_user().await?.into())
+    }
+
+    /// O Gitea não expõe os dados do próprio token
+    async fn token_info(&self) -> Result<Option<TokenInfo>> {
+        Ok(None)
+    }
+
+    /// Organizações do usuário (`GET /user/orgs`); o Gitea não usa ETag nas listagens
+    async fn containers(&self, limit: usize, _etag: Option<&str>) -> Result<Fetched<Group>> {
+        let orgs = self
+            .list::<Vec<Organization>, Organization>(&self.list_url("/user/orgs")?, limit)
+            .await?;
+        Ok(Fetched::Items(orgs.into_iter().map(Group::from).collect(), None))
+    }
+
+    /// Repositórios da organização (`GET /repos/search?uid=`)
+    async fn projects(&self, container_id: i64, limit: usize, _etag: Option<&str>) -> Result<Fetched<Project>> {
+        let mut url = self.list_url("/repos/search")?;
+        url.query_pairs_mut()
+            .append_pair("uid", &container_id.to_string())
+            .append_pair("exclusive", "true");
+        let repos = self.list::<SearchResults<Repository>, Repository>(&url, limit).await?;
+        Ok(Fetched::Items(repos.into_iter().map(Project::from).collect(), None))
+    }
+
+    /// `GET /repos/:dono/:nome/issues`
+    async fn issues(
+        &self,
+        project_id: i64,
+        filter: &IssueFilter,
+        _user_id: i64,
+        limit: usize,
+        _etag: Option<&str>,
+    ) -> Result<Fetched<Issue>> {
+        Ok(Fetched::Items(self.list_issues(project_id, filter, limit).await?, None))
+    }
+
+    async fn find_issue(&self, project_id: i64, issue_id: i64) -> Result<Issue> {
+        let filter = IssueFilter { state: Some("all".into()), ..Default::default() };
+        self.list_issues(project_id, &filter, usize::MAX)
+            .await?
+            .into_iter()
+            .find(|issue| issue.id == issue_id)
+            .ok_or_else(|| Error::NotFound(format!("Issue {} não encontrada no projeto {}", issue_id, project_id)))
+    }
+
+    /// `POST /repos/:dono/:nome/issues/:index/times`; o Gitea não guarda descrição no lançamento.
+    ///
+    /// Tempo negativo (sessão encurtada depois de enviada) é descontado apagando lançamentos
+    /// do usuário, já que a API recusa valores negativos.
+    async fn log_time(&self, project_id: i64, issue_iid: i64, seconds: i64, _summary: &str) -> Result<TimeStats> {
+        let path = self.issue_path(project_id, issue_iid).await?;
+        if seconds < 0 {
+            self.remove_time(&path, -seconds).await?;
+        } else {
+            self.add_time(&path, seconds).await?;
+        }
+        self.time_stats(project_id, issue_iid).await
+    }
+
+    /// Soma dos lançamentos da issue (`GET .../times`); o Gitea não tem estimativa
+    async fn time_stats(&self, project_id: i64, issue_iid: i64) -> Result<TimeStats> {
+        let path = self.issue_path(project_id, issue_iid).await?;
+        let url = self.list_url(&format!("{}/times", path))?;
+        let total: i64 = self
+            .list::<Vec<TrackedTime>, TrackedTime>(&url, usize::MAX)
+            .await?
+            .iter()
+            .map(|t| t.time)
+            .sum();
+        Ok(TimeStats {
+            time_estimate: 0,
+            total_time_spent: total,
+            human_time_estimate: None,
+            human_total_time_spent: Some(format_gitlab_time(total)),
+        })
+    }
+
+    /// `GET .../comments`
+    async fn issue_notes(&self, project_id: i64, issue_iid: i64) -> Result<Vec<Note>> {
+        let path = self.issue_path(project_id, issue_iid).await?;
+        let comments = self
+            .list::<Vec<Comment>, Comment>(&self.list_url(&format!("{}/comments", path))?, usize::MAX)
+            .await?;
+        Ok(comments.into_iter().map(Note::from).collect())
+    }
+}
+
+/// Traduz o filtro para os parâmetros de `/repos/:dono/:nome/issues`
+fn apply_filter(filter: &IssueFilter, url: &mut Url, login: Option<&str>) {
+    let mut query = url.query_pairs_mut();
+    query.append_pair("type", "issues");
+    for (key, value) in filter.text_params() {
+        let key = match key {
+            "search" => "q",
+            "milestone" => "milestones",
+            "scope" => continue, // não existe no Gitea
+            other => other,
+        };
+        let value = if key == "state" && value == "opened" { "open" } else { value };
+        query.append_pair(key, value);
+    }
+    if let Some(login) = login {
+        query.append_pair("assigned_by", login);
+    }
+}
+
+#[derive(Deserialize)]
+struct GiteaUser {
+    id: i64,
+    login: String,
+    #[serde(default)]
+    full_name: String,
+    #[serde(default)]
+    html_url: Option<String>,
+    #[serde(default)]
+    avatar_url: Option<String>,
+}
+
+impl From<GiteaUser> for User {
+    fn from(user: GiteaUser) -> Self {
+        User {
+            id: user.id,
+            name: if user.full_name.is_empty() { user.login.clone() } else { user.full_name },
+            username: user.login,
+            web_url: user.html_url,
+            avatar_url: user.avatar_url,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Organization {
+    id: i64,
+    /// `username` nas versões antigas, `name` nas novas
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    full_name: String,
+}
+
+impl From<Organization> for Group {
+    fn from(org: Organization) -> Self {
+        let path = org.name.or(org.username).unwrap_or_default();
+        Group {
+            id: org.id,
+            full_name: if org.full_name.is_empty() { path.clone() } else { org.full_name },
+            name: path.clone(),
+            full_path: path,
+            parent_id: None,
+            web_url: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Repository {
+    id: i64,
+    name: String,
+    full_name: String,
+    #[serde(default)]
+    html_url: Option<String>,
+}
+
+impl From<Repository> for Project {
+    fn from(repo: Repository) -> Self {
+        Project {
+            id: repo.id,
+            name_with_namespace: repo.full_name.replace('/', " / "),
+            path_with_namespace: repo.full_name,
+            name: repo.name,
+            web_url: repo.html_url,
+        }
+    }
+}
+
+/// Envelope de `/repos/search`
+#[derive(Deserialize)]
+struct SearchResults<T> {
+    data: Vec<T>,
+}
+
+impl<T> From<SearchResults<T>> for Vec<T> {
+    fn from(results: SearchResults<T>) -> Self {
+        results.data
+    }
+}
+
+#[derive(Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaMilestone {
+    id: i64,
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaIssue {
+    id: i64,
+    number: i64,
+    title: String,
+    state: String,
+    html_url: String,
+    #[serde(default)]
+    labels: Vec<Label>,
+    #[serde(default)]
+    milestone: Option<GiteaMilestone>,
+    #[serde(default)]
+    assignees: Option<Vec<GiteaUser>>,
+}
+
+impl GiteaIssue {
+    fn into_issue(self, project_id: i64) -> Issue {
+        Issue {
+            id: self.id,
+            iid: self.number,
+            project_id,
+            title: self.title,
+            state: if self.state == "open" { "opened".into() } else { self.state },
+            web_url: self.html_url,
+            labels: self.labels.into_iter().map(|l| l.name).collect(),
+            milestone: self.milestone.map(|m| Milestone { id: m.id, title: m.title }),
+            assignees: self.assignees.unwrap_or_default().into_iter().map(User::from).collect(),
+            time_stats: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TrackedTime {
+    id: i64,
+    time: i64,
+}
+
+#[derive(Deserialize)]
+struct Comment {
+    id: i64,
+    body: String,
+    user: GiteaUser,
+    created_at: String,
+}
+
+impl From<Comment> for Note {
+    fn from(comment: Comment) -> Self {
+        Note {
+            id: comment.id,
+            body: comment.body,
+            author: comment.user.into(),
+            created_at: comment.created_at,
+            system: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_uses_gitea_names() {
+        let filter = IssueFilter {
+            search: Some("login".into()),
+            milestone: Some("v1".into()),
+            scope: Some("all".into()),
+            ..IssueFilter::assigned_to_me()
+        };
+        let mut url = Url::parse("https://gitea.example.com/api/v1/repos/org/app/issues?limit=50").unwrap();
+        apply_filter(&filter, &mut url, Some("dev"));
+
+        assert_eq!(
+            url.query(),
+            Some("limit=50&type=issues&q=login&milestones=v1&state=open&assigned_by=dev")
+        );
+    }
+}
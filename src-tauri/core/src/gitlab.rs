@@ -0,0 +1,292 @@
+
+use crate::backend::{Fetched, TrackerBackend};
+use crate::error::{Error, Result};
+use crate::http::{check, next_page};
+use crate::models::{Group, Issue, IssueFilter, Note, Project, TimeStats, TokenInfo, User};
+use crate::oauth::OAuthSession;
+use crate::tracker::format_gitlab_time;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::{RequestBuilder, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+/// Itens por página pedidos ao GitLab (máximo aceito pela API)
+const PER_PAGE: &str = "100";
+
+/// Escopos que o token precisa para lançar tempo (`add_spent_time` exige escrita)
+pub const REQUIRED_SCOPES: &[&str] = &["api"];
+
+/// Escopos de `REQUIRED_SCOPES` que faltam no token
+pub fn missing_scopes(token: &TokenInfo) -> Vec<String> {
+    REQUIRED_SCOPES
+        .iter()
+        .filter(|scope| !token.scopes.iter().any(|s| s == *scope))
+        .map(|scope| scope.to_string())
+        .collect()
+}
+
+/// Credencial enviada como `Authorization: Bearer`
+enum Auth {
+    /// Personal access token
+    Token(String),
+    /// Login OAuth, renovado quando a API responde 401
+    OAuth(Arc<OAuthSession>),
+}
+
+/// Acesso à API v4 de uma instância do GitLab
+pub struct GitLabClient {
+    http: reqwest::Client,
+    base_url: String,
+    auth: Auth,
+}
+
+impl GitLabClient {
+    pub fn new(http: &reqwest::Client, base_url: &str, token: &str) -> Self {
+        GitLabClient {
+            http: http.clone(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            auth: Auth::Token(token.to_string()),
+        }
+    }
+
+    /// Cliente autenticado pela sessão OAuth (a URL base é a da sessão); a sessão é
+    /// compartilhada entre os clientes do mesmo perfil para renovar uma vez só
+    pub fn with_oauth(http: &reqwest::Client, session: Arc<OAuthSession>) -> Self {
+        GitLabClient {
+            http: http.clone(),
+            base_url: session.base_url().to_string(),
+            auth: Auth::OAuth(session),
+        }
+    }
+
+    /// Envia a requisição montada por `request` com o token atual; no OAuth, um 401
+    /// renova o token e repete uma vez
+    async fn send(&self, request: impl Fn(&str) -> RequestBuilder) -> Result<Response> {
+        let token = match &self.auth {
+            Auth::Token(token) => token.clone(),
+            Auth::OAuth(session) => session.access_token().await?,
+        };
+        let resp = request(&token).send().await?;
+        match &self.auth {
+            Auth::OAuth(session) if resp.status() == StatusCode::UNAUTHORIZED => {
+                let token = session.refresh_after(&token).await?;
+                Ok(request(&token).send().await?)
+            }
+            _ => Ok(resp),
+        }
+    }
+
+    fn url(&self, path: &str) -> Result<Url> {
+        Url::parse(&format!("{}/api/v4{}", self.base_url, path))
+            .map_err(|e| Error::InvalidState(format!("URL do GitLab inválida: {}", e)))
+    }
+
+    /// URL de listagem já pedindo o maior tamanho de página aceito pelo GitLab
+    fn list_url(&self, path: &str) -> Result<Url> {
+        let mut url = self.url(path)?;
+        url.query_pairs_mut().append_pair("per_page", PER_PAGE);
+        Ok(url)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: Url) -> Result<T> {
+        let resp = self.send(|token| self.http.get(url.clone()).bearer_auth(token)).await?;
+        Ok(check(resp).await?.json().await?)
+    }
+
+    /// Busca a listagem completa, até `limit` itens; com `etag`, a primeira página é condicional.
+    ///
+    /// O ETag só é devolvido quando a listagem cabe numa página: o 304 da primeira página
+    /// não diz nada sobre as seguintes.
+    async fn list<T: DeserializeOwned>(&self, url: &Url, limit: usize, etag: Option<&str>) -> Result<Fetched<T>> {
+        let resp = self
+            .send(|token| {
+                let request = self.http.get(url.clone()).bearer_auth(token);
+                match etag {
+                    Some(etag) => request.header(IF_NONE_MATCH, etag),
+                    None => request,
+                }
+            })
+            .await?;
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Fetched::NotModified);
+        }
+        let resp = check(resp).await?;
+
+        let mut next = next_page(resp.headers(), url);
+        let etag = resp
+            .headers()
+            .get(ETAG)
+            .filter(|_| next.is_none())
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let mut items: Vec<T> = resp.json().await?;
+
+        while let Some(page_url) = next.take() {
+            if items.is_empty() || items.len() >= limit {
+                break;
+            }
+            let resp = self.send(|token| self.http.get(page_url.clone()).bearer_auth(token)).await?;
+            let resp = check(resp).await?;
+            next = next_page(resp.headers(), &page_url);
+
+            let page: Vec<T> = resp.json().await?;
+            if page.is_empty() {
+                break;
+            }
+            items.extend(page);
+        }
+        items.truncate(limit);
+        Ok(Fetched::Items(items, etag))
+    }
+}
+
+impl TrackerBackend for GitLabClient {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// `GET /user`
+    async fn current_user(&self) -> Result<User> {
+        self.get(self.url("/user")?).await
+    }
+
+    /// `GET /personal_access_tokens/self`; no login OAuth (escopo pedido por nós e
+    /// renovação automática) e em instâncias anteriores ao 15.5 não há o que consultar
+    async fn token_info(&self) -> Result<Option<TokenInfo>> {
+        if let Auth::OAuth(_) = self.auth {
+            return Ok(None);
+        }
+        match self.get(self.url("/personal_access_tokens/self")?).await {
+            Ok(info) => Ok(Some(info)),
+            Err(Error::GitLab { status: 404, .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// `GET /groups`
+    async fn containers(&self, limit: usize, etag: Option<&str>) -> Result<Fetched<Group>> {
+        self.list(&self.list_url("/groups")?, limit, etag).await
+    }
+
+    /// `GET /groups/:id/projects`
+    async fn projects(&self, container_id: i64, limit: usize, etag: Option<&str>) -> Result<Fetched<Project>> {
+        let url = self.list_url(&format!("/groups/{}/projects", container_id))?;
+        self.list(&url, limit, etag).await
+    }
+
+    /// `GET /projects/:id/issues` com os filtros como parâmetros
+    async fn issues(
+        &self,
+        project_id: i64,
+        filter: &IssueFilter,
+        user_id: i64,
+        limit: usize,
+        etag: Option<&str>,
+    ) -> Result<Fetched<Issue>> {
+        let mut url = self.list_url(&format!("/projects/{}/issues", project_id))?;
+        apply_filter(filter, &mut url, user_id);
+        self.list(&url, limit, etag).await
+    }
+
+    /// Busca a issue no projeto a partir do `id` global.
+    ///
+    /// `GET /issues/:id` resolve numa chamada, mas só para administradores. Para os demais
+    /// usuários a API não filtra por `id`, então as issues do projeto são percorridas página
+    /// a página até a que contém a issue (no pior caso, o projeto inteiro). Só é usada para
+    /// sessões antigas, sem `issue_iid`: as demais vão direto pelo `iid`.
+    async fn find_issue(&self, project_id: i64, issue_id: i64) -> Result<Issue> {
+        match self.get::<Issue>(self.url(&format!("/issues/{}", issue_id))?).await {
+            Ok(issue) if issue.project_id == project_id => return Ok(issue),
+            Ok(_) | Err(Error::Auth(_)) | Err(Error::GitLab { status: 403 | 404, .. }) => {}
+            Err(e) => return Err(e),
+        }
+
+        let mut url = self.list_url(&format!("/projects/{}/issues", project_id))?;
+        url.query_pairs_mut().append_pair("scope", "all");
+        let mut next = Some(url);
+        while let Some(page_url) = next.take() {
+            let resp = check(self.send(|token| self.http.get(page_url.clone()).bearer_auth(token)).await?).await?;
+            next = next_page(resp.headers(), &page_url);
+            let page: Vec<Issue> = resp.json().await?;
+            if let Some(issue) = page.into_iter().find(|issue| issue.id == issue_id) {
+                return Ok(issue);
+            }
+        }
+        Err(Error::NotFound(format!("Issue {} não encontrada no projeto {}", issue_id, project_id)))
+    }
+
+    /// Lança tempo gasto na issue (`POST /projects/:id/issues/:iid/add_spent_time`)
+    async fn log_time(&self, project_id: i64, issue_iid: i64, seconds: i64, summary: &str) -> Result<TimeStats> {
+        let duration = format_gitlab_time(seconds);
+        let url = self.url(&format!("/projects/{}/issues/{}/add_spent_time", project_id, issue_iid))?;
+        let resp = self
+            .send(|token| {
+                self.http
+                    .post(url.clone())
+                    .query(&[("duration", duration.as_str()), ("summary", summary)])
+                    .bearer_auth(token)
+            })
+            .await?;
+        Ok(check(resp).await?.json().await?)
+    }
+
+    /// Tempo estimado e gasto na issue (`GET /projects/:id/issues/:iid/time_stats`)
+    async fn time_stats(&self, project_id: i64, issue_iid: i64) -> Result<TimeStats> {
+        self.get(self.url(&format!("/projects/{}/issues/{}/time_stats", project_id, issue_iid))?)
+            .await
+    }
+
+    /// Comentários da issue (`GET /projects/:id/issues/:iid/notes`)
+    async fn issue_notes(&self, project_id: i64, issue_iid: i64) -> Result<Vec<Note>> {
+        let url = self.list_url(&format!("/projects/{}/issues/{}/notes", project_id, issue_iid))?;
+        Ok(self.list(&url, usize::MAX, None).await?.into_items())
+    }
+}
+
+/// Traduz o filtro para os parâmetros de `/projects/:id/issues`
+fn apply_filter(filter: &IssueFilter, url: &mut Url, user_id: i64) {
+    let assignee_id = match filter.assignee_id {
+        Some(id) => Some(id),
+        None if filter.assigned_to_me && user_id > 0 => Some(user_id),
+        None => None,
+    };
+
+    let mut query = url.query_pairs_mut();
+    for (key, value) in filter.text_params() {
+        query.append_pair(key, value);
+    }
+    if let Some(id) = assignee_id {
+        query.append_pair("assignee_id", &id.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn default_filter_is_my_open_issues() {
+        let mut issues = url("https://gitlab.example.com/api/v4/projects/1/issues?per_page=100");
+        apply_filter(&IssueFilter::assigned_to_me(), &mut issues, 42);
+
+        assert_eq!(issues.query(), Some("per_page=100&state=opened&assignee_id=42"));
+    }
+
+    #[test]
+    fn filter_skips_empty_values() {
+        let filter = IssueFilter {
+            search: Some("login bug".into()),
+            labels: Some("".into()),
+            scope: Some("all".into()),
+            ..Default::default()
+        };
+        let mut issues = url("https://gitlab.example.com/api/v4/projects/1/issues");
+        apply_filter(&filter, &mut issues, 42);
+
+        assert_eq!(issues.query(), Some("search=login+bug&scope=all"));
+    }
+}
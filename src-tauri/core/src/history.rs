@@ -0,0 +1,149 @@
+use crate::editing::Interval;
+use crate::error::Result;
+use crate::tracker::{Clock, Tracker};
+use rusqlite::{named_params, Row};
+use serde::{Deserialize, Serialize};
+
+/// Quantidade de sessões por página quando o filtro não diz
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+/// Filtros do histórico; campos vazios não filtram
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct HistoryFilter {
+    pub profile_id: Option<i64>,
+    /// Sessões com tempo a partir deste instante (segundos Unix)
+    pub from: Option<i64>,
+    /// Sessões com tempo antes deste instante (segundos Unix)
+    pub to: Option<i64>,
+    pub project_id: Option<i64>,
+    pub issue_id: Option<i64>,
+    pub label: Option<String>,
+    /// `runner`, `pause` ou `finalizado`
+    pub status: Option<String>,
+    /// `pendente`, `sincronizado` ou `alterado`
+    pub sync_status: Option<String>,
+    pub offset: i64,
+    /// Sessões por página (padrão 50, no máximo 500)
+    pub limit: Option<i64>,
+}
+
+/// Sessão do histórico com seus intervalos e o tempo somado
+#[derive(Serialize, Debug, Clone)]
+pub struct SessionEntry {
+    pub id: i64,
+    pub profile_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub project_id: Option<i64>,
+    pub issue_id: Option<i64>,
+    pub issue_iid: Option<i64>,
+    pub issue_title: Option<String>,
+    pub issue_web_url: Option<String>,
+    pub label: Option<String>,
+    pub status: Option<String>,
+    pub sync_status: Option<String>,
+    pub manual: bool,
+    pub started_at: Option<i64>,
+    pub ended_at: Option<i64>,
+    /// Soma dos intervalos; o aberto conta até agora
+    pub total_seconds: i64,
+    /// Quanto já foi lançado no rastreador
+    pub synced_seconds: i64,
+    pub intervals: Vec<Interval>,
+}
+
+/// Uma página do histórico e o total de sessões que atendem ao filtro
+#[derive(Serialize, Debug, Clone)]
+pub struct HistoryPage {
+    pub total: i64,
+    pub sessions: Vec<SessionEntry>,
+}
+
+/// Condições comuns à contagem e à página; o período pega sessões com algum intervalo dentro dele
+const HISTORY_WHERE: &str = "
+    WHERE (:profile_id IS NULL OR s.profile_id = :profile_id)
+      AND (:project_id IS NULL OR s.project_id = :project_id)
+      AND (:issue_id IS NULL OR s.issue_id = :issue_id)
+      AND (:label IS NULL OR s.label = :label)
+      AND (:status IS NULL OR s.status = :status)
+      AND (:sync_status IS NULL OR s.sync_status = :sync_status)
+      AND ((:from IS NULL AND :to IS NULL) OR EXISTS (
+            SELECT 1 FROM session_intervals i
+            WHERE i.session_id = s.id
+              AND (:from IS NULL OR COALESCE(i.end_time, :now) > :from)
+              AND (:to IS NULL OR i.start_time < :to)))";
+
+impl<C: Clock> Tracker<'_, C> {
+    /// Sessões mais recentes primeiro, filtradas e paginadas
+    pub fn history(&self, filter: &HistoryFilter) -> Result<HistoryPage> {
+        let now = self.now();
+        let label = filter.label.as_deref().map(str::trim).filter(|v| !v.is_empty());
+        let status = filter.status.as_deref().filter(|v| !v.is_empty());
+        let sync_status = filter.sync_status.as_deref().filter(|v| !v.is_empty());
+        let params = named_params! {
+            ":profile_id": filter.profile_id,
+            ":project_id": filter.project_id,
+            ":issue_id": filter.issue_id,
+            ":label": label,
+            ":status": status,
+            ":sync_status": sync_status,
+            ":from": filter.from,
+            ":to": filter.to,
+            ":now": now,
+        };
+
+        let total = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM sessions s {}", HISTORY_WHERE),
+            params,
+            |row| row.get(0),
+        )?;
+
+        let limit = filter.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT s.id, s.profile_id, s.group_id, s.project_id, s.issue_id, s.issue_iid, s.issue_title,
+                    s.issue_web_url, s.label, s.status, s.sync_status, s.manual, s.synced_seconds,
+                    t.started_at, t.ended_at, COALESCE(t.seconds, 0)
+             FROM sessions s
+             LEFT JOIN (SELECT session_id, MIN(start_time) AS started_at,
+                               MAX(COALESCE(end_time, :now)) AS ended_at,
+                               SUM(COALESCE(end_time, :now) - start_time) AS seconds
+                        FROM session_intervals GROUP BY session_id) t ON t.session_id = s.id
+             {}
+             ORDER BY COALESCE(t.started_at, 0) DESC, s.id DESC
+             LIMIT {} OFFSET {}",
+            HISTORY_WHERE,
+            limit,
+            filter.offset.max(0)
+        ))?;
+        let mut sessions = stmt
+            .query_map(params, session_entry)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for session in &mut sessions {
+            session.intervals = self.intervals(session.id)?;
+        }
+        Ok(HistoryPage { total, sessions })
+    }
+}
+
+fn session_entry(row: &Row) -> rusqlite::Result<SessionEntry> {
+    Ok(SessionEntry {
+        id: row.get(0)?,
+        profile_id: row.get(1)?,
+        group_id: row.get(2)?,
+        project_id: row.get(3)?,
+        issue_id: row.get(4)?,
+        issue_iid: row.get(5)?,
+        issue_title: row.get(6)?,
+        issue_web_url: row.get(7)?,
+        label: row.get(8)?,
+        status: row.get(9)?,
+        sync_status: row.get(10)?,
+        manual: row.get(11)?,
+        synced_seconds: row.get(12)?,
+        started_at: row.get(13)?,
+        ended_at: row.get(14)?,
+        total_seconds: row.get(15)?,
+        intervals: Vec::new(),
+    })
+}
@@ -0,0 +1,224 @@
+//! Cliente HTTP compartilhado (certificados, proxy, timeouts) e utilitários
+//! comuns aos backends
+
+use crate::error::{Error, Result};
+use reqwest::header::{HeaderMap, LINK};
+use reqwest::{Certificate, ClientBuilder, Identity, NoProxy, Proxy, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::Duration;
+
+/// Timeout padrão de cada requisição
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Timeout padrão para abrir a conexão
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+
+/// Configuração de rede aplicada ao cliente HTTP único (linha `http` da tabela `settings`)
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct HttpSettings {
+    /// Arquivos PEM com certificados raiz extras (ex.: CA interna da empresa)
+    pub ca_certs: Vec<String>,
+    /// Certificado do cliente (PEM) para mTLS
+    pub client_cert: Option<String>,
+    /// Chave privada (PEM) do certificado do cliente; pode estar no mesmo arquivo
+    pub client_key: Option<String>,
+    /// Proxy para todas as requisições; sem ele valem `HTTPS_PROXY`/`HTTP_PROXY`
+    pub proxy_url: Option<String>,
+    /// Hosts que não passam pelo proxy, separados por vírgula
+    pub no_proxy: String,
+    pub timeout_secs: Option<u64>,
+    pub connect_timeout_secs: Option<u64>,
+    /// Aceita qualquer certificado do servidor. Só para emergências: expõe o token
+    /// a quem estiver no meio do caminho.
+    pub accept_invalid_certs: bool,
+}
+
+/// Builder já com a configuração aplicada; os testes ajustam o resto
+pub fn builder(settings: &HttpSettings) -> Result<ClientBuilder> {
+    // o reqwest usa o provedor de criptografia do processo; instalar de novo é inofensivo
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let timeout = settings.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
+    let connect_timeout = settings.connect_timeout_secs.unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS);
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(timeout))
+        .connect_timeout(Duration::from_secs(connect_timeout));
+
+    let mut roots = Vec::new();
+    for path in settings.ca_certs.iter().filter(|p| !p.trim().is_empty()) {
+        let certs = Certificate::from_pem_bundle(&read_pem(path)?)
+            .map_err(|e| invalid(&format!("Certificado {} inválido", path), e))?;
+        if certs.is_empty() {
+            return Err(Error::InvalidState(format!("Nenhum certificado encontrado em {}", path)));
+        }
+        roots.extend(certs);
+    }
+    if !roots.is_empty() {
+        builder = builder.tls_certs_merge(roots);
+    }
+
+    if let Some(cert) = non_empty(&settings.client_cert) {
+        let mut pem = read_pem(cert)?;
+        if let Some(key) = non_empty(&settings.client_key) {
+            pem.push(b'\n');
+            pem.extend(read_pem(key)?);
+        }
+        let identity = Identity::from_pem(&pem).map_err(|e| invalid("Certificado do cliente inválido", e))?;
+        builder = builder.identity(identity);
+    }
+
+    if let Some(url) = non_empty(&settings.proxy_url) {
+        let proxy = Proxy::all(url)
+            .map_err(|e| invalid("Proxy inválido", e))?
+            .no_proxy(NoProxy::from_string(&settings.no_proxy));
+        builder = builder.proxy(proxy);
+    }
+
+    if settings.accept_invalid_certs {
+        eprintln!(
+            "ATENÇÃO: verificação de certificados TLS DESATIVADA. \
+             Qualquer um entre você e o servidor pode ler o token."
+        );
+        builder = builder.tls_danger_accept_invalid_certs(true);
+    }
+    Ok(builder)
+}
+
+/// Cliente HTTP com a configuração de rede
+pub fn client(settings: &HttpSettings) -> Result<reqwest::Client> {
+    builder(settings)?.build().map_err(|e| invalid("Configuração de rede inválida", e))
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| Error::InvalidState(format!("Não foi possível ler {}: {}", path, e)))
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn invalid(what: &str, e: reqwest::Error) -> Error {
+    Error::InvalidState(format!("{}: {}", what, e))
+}
+
+/// Próxima página indicada pelo servidor: `Link: <...>; rel="next"` ou, na falta dele, `X-Next-Page`
+pub(crate) fn next_page(headers: &HeaderMap, current: &Url) -> Option<Url> {
+    let from_link = headers
+        .get(LINK)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value.split(',').find_map(|part| {
+                let (target, params) = part.split_once(';')?;
+                let is_next = params.split(';').any(|p| p.trim() == "rel=\"next\"");
+                let target = target.trim().strip_prefix('<')?.strip_suffix('>')?;
+                if is_next { Url::parse(target).ok() } else { None }
+            })
+        });
+    if from_link.is_some() {
+        return from_link;
+    }
+
+    let page = headers
+        .get("x-next-page")
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())?;
+
+    let mut url = current.clone();
+    let pairs: Vec<(String, String)> = current
+        .query_pairs()
+        .filter(|(key, _)| key != "page")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(pairs).append_pair("page", page);
+    Some(url)
+}
+
+/// Converte uma resposta de erro da API em `Error`
+fn api_error(status: StatusCode, body: String) -> Error {
+    match status.as_u16() {
+        401 | 403 => Error::Auth(format!("{} {}", status, body)),
+        code => Error::GitLab { status: code, body },
+    }
+}
+
+pub(crate) async fn check(resp: reqwest::Response) -> Result<reqwest::Response> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    Err(api_error(status, resp.text().await.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn default_settings_build_a_client() {
+        assert!(client(&HttpSettings::default()).is_ok());
+    }
+
+    #[test]
+    fn missing_or_invalid_certificate_is_reported() {
+        let missing = HttpSettings { ca_certs: vec!["/nao/existe/ca.pem".into()], ..Default::default() };
+        assert!(matches!(client(&missing), Err(Error::InvalidState(msg)) if msg.contains("/nao/existe/ca.pem")));
+
+        let path = std::env::temp_dir().join(format!("ttd-not-a-cert-{}.pem", std::process::id()));
+        fs::write(&path, "não é um certificado").unwrap();
+        let garbage = HttpSettings { ca_certs: vec![path.display().to_string()], ..Default::default() };
+        assert!(matches!(client(&garbage), Err(Error::InvalidState(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn invalid_proxy_is_reported() {
+        let settings = HttpSettings { proxy_url: Some("::não é url".into()), ..Default::default() };
+        assert!(matches!(client(&settings), Err(Error::InvalidState(msg)) if msg.starts_with("Proxy")));
+    }
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn follows_link_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(LINK, HeaderValue::from_static(
+            "<https://gitlab.example.com/api/v4/groups?page=1&per_page=100>; rel=\"first\", \
+             <https://gitlab.example.com/api/v4/groups?page=3&per_page=100>; rel=\"next\"",
+        ));
+        let current = url("https://gitlab.example.com/api/v4/groups?per_page=100&page=2");
+
+        assert_eq!(
+            next_page(&headers, &current).unwrap().as_str(),
+            "https://gitlab.example.com/api/v4/groups?page=3&per_page=100"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_next_page_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-next-page", HeaderValue::from_static("2"));
+        let current = url("https://gitlab.example.com/api/v4/projects/1/issues?per_page=100&scope=all");
+
+        assert_eq!(
+            next_page(&headers, &current).unwrap().as_str(),
+            "https://gitlab.example.com/api/v4/projects/1/issues?per_page=100&scope=all&page=2"
+        );
+    }
+
+    #[test]
+    fn stops_on_last_page() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-next-page", HeaderValue::from_static(""));
+        headers.insert(LINK, HeaderValue::from_static(
+            "<https://gitlab.example.com/api/v4/groups?page=1>; rel=\"first\"",
+        ));
+
+        assert!(next_page(&headers, &url("https://gitlab.example.com/api/v4/groups?page=3")).is_none());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::error::{Error, Result};
+use crate::models::IssueRef;
+use crate::state::TimerState;
+use crate::sync;
+use crate::tracker::{Clock, Tracker};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Fonte do tempo sem teclado nem mouse do usuário
+pub trait IdleSource: Send {
+    /// Segundos desde a última interação, ou `None` se o sistema não informa
+    fn idle_seconds(&self) -> Result<Option<i64>>;
+}
+
+/// Para sistemas sem fonte conhecida: a detecção fica desligada
+pub struct NoIdleSource;
+
+impl IdleSource for NoIdleSource {
+    fn idle_seconds(&self) -> Result<Option<i64>> {
+        Ok(None)
+    }
+}
+
+/// Fonte do sistema: D-Bus no Linux (com a feature `idle`), nenhuma nos demais
+pub fn system_source() -> Box<dyn IdleSource> {
+    #[cfg(all(feature = "idle", target_os = "linux"))]
+    return Box::new(dbus::DbusIdle::connect());
+    #[cfg(not(all(feature = "idle", target_os = "linux")))]
+    Box::new(NoIdleSource)
+}
+
+/// Bloco de tempo ausente separado do resto da sessão
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct IdleBlock {
+    pub session_id: i64,
+    pub interval_id: i64,
+    pub start_time: i64,
+    /// Vazio enquanto o usuário ainda não voltou
+    pub end_time: Option<i64>,
+    pub seconds: i64,
+}
+
+/// O que mudou numa leitura do tempo ocioso
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdleEvent {
+    /// Passou do limite: o intervalo foi dividido no início da ausência
+    Started(IdleBlock),
+    /// O usuário voltou: o bloco foi fechado e aguarda decisão
+    Ended(IdleBlock),
+}
+
+/// Decisão do usuário sobre o tempo ausente
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum IdleDecision {
+    /// Conta o tempo na sessão
+    Keep,
+    /// Tira o tempo da sessão
+    Discard,
+    /// Passa o tempo para uma sessão nova em outra issue
+    Reassign {
+        profile_id: i64,
+        group_id: i64,
+        project_id: i64,
+        issue: IssueRef,
+        label: String,
+    },
+}
+
+impl<C: Clock> Tracker<'_, C> {
+    /// Compara o tempo ocioso (`idle`, já lido da `IdleSource`) com o limite e separa ou
+    /// fecha o bloco ausente.
+    ///
+    /// Só age com o timer rodando; `threshold_secs` zero desliga a detecção.
+    pub fn check_idle(&self, idle: i64, threshold_secs: i64) -> Result<Option<IdleEvent>> {
+        if threshold_secs <= 0 {
+            return Ok(None);
+        }
+        let now = self.now();
+        let away = self.open_idle_block()?;
+
+        match away {
+            None if idle >= threshold_secs => Ok(self.begin_idle(now - idle)?.map(IdleEvent::Started)),
+            Some(block) if idle < threshold_secs => {
+                self.end_idle(&block, now - idle)?;
+                Ok(self.idle_block(block.interval_id)?.map(IdleEvent::Ended))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Tempo da sessão que pode ir para o rastreador: os blocos ausentes e o tempo
+    /// recuperado sem decisão ficam de fora até o usuário escolher o que fazer com eles
+    pub fn decided_seconds(&self, session_id: i64) -> Result<i64> {
+        Ok(self.conn.query_row(
+            "SELECT COALESCE(SUM(COALESCE(end_time, ?2) - start_time), 0)
+             FROM session_intervals WHERE session_id=?1 AND idle = 0 AND COALESCE(recovered, 0) = 0",
+            params![session_id, self.now()],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Bloco ausente aguardando decisão, se houver
+    pub fn pending_idle(&self) -> Result<Option<IdleBlock>> {
+        let interval_id: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT id FROM session_intervals WHERE idle = 1 ORDER BY start_time DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match interval_id {
+            Some(interval_id) => self.idle_block(interval_id),
+            None => Ok(None),
+        }
+    }
+
+    /// Aplica a decisão sobre o bloco ausente; com `Reassign` retorna a sessão criada
+    pub fn resolve_idle(&self, interval_id: i64, decision: &IdleDecision) -> Result<Option<i64>> {
+        let block = self
+            .idle_block(interval_id)?
+            .ok_or_else(|| Error::NotFound(format!("Tempo ausente {} não encontrado", interval_id)))?;
+        let tx = self.conn.unchecked_transaction()?;
+        // decidir sem o usuário ter "voltado" pela leitura ociosa: a volta é agora
+        if block.end_time.is_none() {
+            self.end_idle(&block, self.now())?;
+        }
+
+        let reassigned = match decision {
+            IdleDecision::Keep => {
+                self.conn.execute("UPDATE session_intervals SET idle = 0 WHERE id=?1", params![interval_id])?;
+                None
+            }
+            IdleDecision::Discard => {
+                self.conn.execute("DELETE FROM session_intervals WHERE id=?1", params![interval_id])?;
+                None
+            }
+            IdleDecision::Reassign { profile_id, group_id, project_id, issue, label } => {
+                self.conn.execute(
+                    "INSERT INTO sessions (profile_id, group_id, project_id, issue_id, issue_iid, issue_title, issue_web_url, label, status, manual, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'finalizado', 1, ?9)",
+                    params![profile_id, group_id, project_id, issue.id, issue.iid, issue.title, issue.web_url, label, self.now()],
+                )?;
+                let session_id = self.conn.last_insert_rowid();
+                self.conn.execute(
+                    "UPDATE session_intervals SET session_id=?2, idle = 0 WHERE id=?1",
+                    params![interval_id, session_id],
+                )?;
+                sync::enqueue_session(self.conn, session_id)?;
+                Some(session_id)
+            }
+        };
+        sync::enqueue_session(self.conn, block.session_id)?;
+        tx.commit()?;
+        Ok(reassigned)
+    }
+
+    /// Fecha o intervalo rodando em `since` e abre o bloco ausente a partir dali
+    fn begin_idle(&self, since: i64) -> Result<Option<IdleBlock>> {
+        let TimerState::Running { session_id, interval_id } = self.state()? else {
+            return Ok(None);
+        };
+        let start_time: i64 = self.conn.query_row(
+            "SELECT start_time FROM session_intervals WHERE id=?1",
+            params![interval_id],
+            |row| row.get(0),
+        )?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        let idle_id = if since <= start_time {
+            // ausente desde o início do intervalo: ele todo vira o bloco
+            self.conn.execute("UPDATE session_intervals SET idle = 1 WHERE id=?1", params![interval_id])?;
+            interval_id
+        } else {
+            self.conn.execute(
+                "UPDATE session_intervals SET end_time=?2, heartbeat_at=?2 WHERE id=?1",
+                params![interval_id, since],
+            )?;
+            self.conn.execute(
+                "INSERT INTO session_intervals (session_id, start_time, heartbeat_at, idle)
+                 VALUES (?1, ?2, ?3, 1)",
+                params![session_id, since, self.now()],
+            )?;
+            self.conn.last_insert_rowid()
+        };
+        tx.commit()?;
+        self.idle_block(idle_id)
+    }
+
+    /// Fecha o bloco ausente em `back_at`; se a sessão segue rodando, abre um intervalo normal dali
+    fn end_idle(&self, block: &IdleBlock, back_at: i64) -> Result<()> {
+        let back_at = back_at.max(block.start_time);
+        let closed = self.conn.execute(
+            "UPDATE session_intervals SET end_time=?2, heartbeat_at=?2 WHERE id=?1 AND end_time IS NULL",
+            params![block.interval_id, back_at],
+        )?;
+        let status: Option<String> = self.conn.query_row(
+            "SELECT status FROM sessions WHERE id=?1",
+            params![block.session_id],
+            |row| row.get(0),
+        )?;
+        if closed > 0 && status.as_deref() == Some("runner") {
+            self.conn.execute(
+                "INSERT INTO session_intervals (session_id, start_time, heartbeat_at) VALUES (?1, ?2, ?3)",
+                params![block.session_id, back_at, self.now()],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Bloco ausente ainda aberto (o usuário não voltou)
+    fn open_idle_block(&self) -> Result<Option<IdleBlock>> {
+        let interval_id: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT id FROM session_intervals WHERE idle = 1 AND end_time IS NULL LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match interval_id {
+            Some(interval_id) => self.idle_block(interval_id),
+            None => Ok(None),
+        }
+    }
+
+    fn idle_block(&self, interval_id: i64) -> Result<Option<IdleBlock>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT session_id, start_time, end_time, COALESCE(end_time, ?2) - start_time
+                 FROM session_intervals WHERE id=?1 AND idle = 1",
+                params![interval_id, self.now()],
+                |row| {
+                    Ok(IdleBlock {
+                        session_id: row.get(0)?,
+                        interval_id,
+                        start_time: row.get(1)?,
+                        end_time: row.get(2)?,
+                        seconds: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+}
+
+/// Tempo ocioso pelo D-Bus: Mutter (GNOME), ScreenSaver (KDE e outros) e, por último, o logind
+#[cfg(all(feature = "idle", target_os = "linux"))]
+mod dbus {
+    use super::IdleSource;
+    use crate::error::{Error, Result};
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use zbus::blocking::{Connection, Proxy};
+
+    pub struct DbusIdle {
+        session: Option<Connection>,
+        system: Option<Connection>,
+    }
+
+    impl DbusIdle {
+        pub fn connect() -> Self {
+            DbusIdle { session: Connection::session().ok(), system: Connection::system().ok() }
+        }
+
+        /// Milissegundos ociosos segundo o Mutter
+        fn mutter(conn: &Connection) -> zbus::Result<i64> {
+            let reply = conn.call_method(
+                Some("org.gnome.Mutter.IdleMonitor"),
+                "/org/gnome/Mutter/IdleMonitor/Core",
+                Some("org.gnome.Mutter.IdleMonitor"),
+                "GetIdletime",
+                &(),
+            )?;
+            let millis: u64 = reply.body().deserialize()?;
+            Ok((millis / 1000) as i64)
+        }
+
+        fn screensaver(conn: &Connection) -> zbus::Result<i64> {
+            let reply = conn.call_method(
+                Some("org.freedesktop.ScreenSaver"),
+                "/org/freedesktop/ScreenSaver",
+                Some("org.freedesktop.ScreenSaver"),
+                "GetSessionIdleTime",
+                &(),
+            )?;
+            let secs: u32 = reply.body().deserialize()?;
+            Ok(secs as i64)
+        }
+
+        /// `IdleHint` da sessão no logind; só é ligado depois do tempo de bloqueio do ambiente
+        fn logind(conn: &Connection) -> zbus::Result<i64> {
+            let session = Proxy::new(
+                conn,
+                "org.freedesktop.login1",
+                "/org/freedesktop/login1/session/auto",
+                "org.freedesktop.login1.Session",
+            )?;
+            if !session.get_property::<bool>("IdleHint")? {
+                return Ok(0);
+            }
+            let since_micros: u64 = session.get_property("IdleSinceHint")?;
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            Ok(now.saturating_sub(since_micros / 1_000_000) as i64)
+        }
+    }
+
+    impl IdleSource for DbusIdle {
+        fn idle_seconds(&self) -> Result<Option<i64>> {
+            if let Some(session) = &self.session {
+                if let Ok(secs) = Self::mutter(session).or_else(|_| Self::screensaver(session)) {
+                    return Ok(Some(secs));
+                }
+            }
+            match &self.system {
+                Some(system) => Self::logind(system)
+                    .map(Some)
+                    .map_err(|e| Error::Internal(format!("tempo ocioso pelo D-Bus: {}", e))),
+                None => Ok(None),
+            }
+        }
+    }
+}
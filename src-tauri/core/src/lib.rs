@@ -0,0 +1,29 @@
+//! Regras do TraceTimeDev sem dependência do Tauri: sessões de tempo, banco,
+//! fila de envio, cache e clientes dos rastreadores de issues (GitLab, Gitea).
+//!
+//! O app em `src-tauri` só expõe isso como comandos e cuida da bandeja e dos eventos.
+
+pub mod backend;
+pub mod cache;
+pub mod config;
+pub mod editing;
+pub mod error;
+pub mod gitea;
+pub mod gitlab;
+pub mod history;
+pub mod idle;
+pub mod http;
+pub mod migrations;
+pub mod models;
+pub mod oauth;
+pub mod power;
+pub mod recovery;
+pub mod secrets;
+pub mod state;
+pub mod sync;
+pub mod ticker;
+pub mod tracker;
+
+pub use backend::{Backend, BackendKind, TrackerBackend};
+pub use error::{Error, Result};
+pub use tracker::{Clock, SystemClock, Tracker};
@@ -0,0 +1,403 @@
+
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+type Migration = fn(&Connection) -> rusqlite::Result<()>;
+
+/// Migrações em ordem; a posição na lista (+1) é a versão gravada em `PRAGMA user_version`.
+///
+/// Nunca altere ou remova um passo já publicado: adicione um novo no final.
+const MIGRATIONS: &[Migration] = &[
+    baseline,
+    sync_outbox,
+    issue_ref,
+    interval_heartbeat,
+    config_page_limit,
+    gitlab_cache,
+    tracker_backend,
+    profiles,
+    token_hint,
+    oauth,
+    token_expiry,
+    settings,
+    session_editing,
+    interval_idle,
+    session_auto_paused,
+    outbox_sending,
+    cache_single_page_etags,
+];
+
+/// Versão do schema após aplicar todas as migrações
+pub fn latest_version() -> u32 {
+    MIGRATIONS.len() as u32
+}
+
+pub fn current_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Aplica as migrações pendentes, cada uma em sua própria transação.
+///
+/// Se houver migração pendente num banco que já tem dados, uma cópia é gravada
+/// em `backup_to` antes de qualquer alteração. Retorna a versão final do schema.
+pub fn migrate(conn: &mut Connection, backup_to: Option<&Path>) -> rusqlite::Result<u32> {
+    let from = current_version(conn)?;
+    if from >= latest_version() {
+        return Ok(from);
+    }
+
+    if let Some(path) = backup_to {
+        if has_tables(conn)? {
+            backup(conn, path)?;
+        }
+    }
+
+    for (index, step) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        let version = index as u32 + 1;
+        let tx = conn.transaction()?;
+        step(&tx)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+
+    current_version(conn)
+}
+
+fn has_tables(conn: &Connection) -> rusqlite::Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Copia o banco para `path` (sobrescreve uma cópia anterior de mesmo nome)
+fn backup(conn: &Connection, path: &Path) -> rusqlite::Result<()> {
+    if path.exists() {
+        let _ = std::fs::remove_file(path);
+    }
+    conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+    Ok(())
+}
+
+/// Adiciona a coluna apenas se ela ainda não existir
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> rusqlite::Result<()> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{}') WHERE name=?1", table),
+        params![column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// v1: schema original (config, sessions, session_intervals)
+fn baseline(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS config (
+            id INTEGER PRIMARY KEY,
+            url TEXT NOT NULL,
+            token TEXT NOT NULL,
+            labels TEXT DEFAULT '',
+            gitlab_user TEXT,
+            gitlab_user_id INTEGER
+        );
+        CREATE TABLE IF NOT EXISTS sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            group_id INTEGER,
+            project_id INTEGER,
+            issue_id INTEGER,
+            label TEXT,
+            status TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS session_intervals (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER,
+            start_time DATETIME,
+            end_time DATETIME,
+            FOREIGN KEY(session_id) REFERENCES sessions(id)
+        );",
+    )
+}
+
+/// v2: estado de envio ao GitLab e fila de envio
+fn sync_outbox(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "sessions", "sync_status", "TEXT")?;
+    add_column(conn, "sessions", "synced_duration", "TEXT")?;
+    add_column(conn, "sessions", "synced_at", "DATETIME")?;
+    add_column(conn, "sessions", "sync_response", "TEXT")?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sync_outbox (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER UNIQUE,
+            duration TEXT NOT NULL,
+            summary TEXT,
+            attempts INTEGER DEFAULT 0,
+            next_attempt_at INTEGER DEFAULT (strftime('%s','now')),
+            last_error TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY(session_id) REFERENCES sessions(id)
+        );",
+    )
+}
+
+/// v3: iid, título e link da issue na sessão
+fn issue_ref(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "sessions", "issue_iid", "INTEGER")?;
+    add_column(conn, "sessions", "issue_title", "TEXT")?;
+    add_column(conn, "sessions", "issue_web_url", "TEXT")
+}
+
+/// v4: heartbeat e marcação de tempo recuperado nos intervalos
+fn interval_heartbeat(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "session_intervals", "heartbeat_at", "INTEGER")?;
+    add_column(conn, "session_intervals", "recovered", "INTEGER DEFAULT 0")
+}
+
+/// v5: limite de itens buscados nas listagens paginadas do GitLab
+fn config_page_limit(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "config", "page_limit", "INTEGER DEFAULT 1000")
+}
+
+/// v6: cache das listagens de grupos, projetos e issues
+fn gitlab_cache(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS gitlab_cache (
+            cache_key TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            parent_id INTEGER,
+            items TEXT NOT NULL,
+            etag TEXT,
+            fetched_at INTEGER NOT NULL
+        );",
+    )
+}
+
+/// v7: rastreador configurável e duração do lançamento em segundos.
+///
+/// As chaves do cache passam a incluir a instância, então o cache antigo é descartado.
+fn tracker_backend(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "config", "backend_kind", "TEXT DEFAULT 'gitlab'")?;
+    add_column(conn, "sync_outbox", "seconds", "INTEGER")?;
+    conn.execute("DELETE FROM gitlab_cache", []).map(|_| ())
+}
+
+/// v8: vários perfis (instância + conta) ao mesmo tempo.
+///
+/// A configuração única vira o primeiro perfil e as sessões existentes passam a
+/// pertencer a ele; a tabela `config` fica como estava.
+fn profiles(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            backend_kind TEXT NOT NULL DEFAULT 'gitlab',
+            url TEXT NOT NULL,
+            token TEXT NOT NULL,
+            labels TEXT,
+            gitlab_user TEXT,
+            gitlab_user_id INTEGER,
+            page_limit INTEGER DEFAULT 1000
+        );
+        INSERT INTO profiles (name, backend_kind, url, token, labels, gitlab_user, gitlab_user_id, page_limit)
+        SELECT url, COALESCE(backend_kind, 'gitlab'), url, token, labels, gitlab_user, gitlab_user_id, page_limit
+        FROM config
+        WHERE NOT EXISTS (SELECT 1 FROM profiles);",
+    )?;
+    add_column(conn, "sessions", "profile_id", "INTEGER")?;
+    conn.execute(
+        "UPDATE sessions SET profile_id=(SELECT MIN(id) FROM profiles) WHERE profile_id IS NULL",
+        [],
+    )?;
+    // as chaves do cache passam a começar pelo perfil
+    conn.execute("DELETE FROM gitlab_cache", []).map(|_| ())
+}
+
+/// v9: tokens saem do banco (vão para o chaveiro ou arquivo cifrado na inicialização);
+/// o banco guarda só a forma mascarada. A cópia antiga em `config` é apagada.
+fn token_hint(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "profiles", "token_hint", "TEXT")?;
+    conn.execute("UPDATE config SET token=''", []).map(|_| ())
+}
+
+/// v10: perfis autenticados por login OAuth em vez de personal access token
+fn oauth(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "profiles", "auth_kind", "TEXT NOT NULL DEFAULT 'token'")?;
+    add_column(conn, "profiles", "oauth_client_id", "TEXT")
+}
+
+/// v11: validade do token de cada perfil, para avisar antes de expirar
+fn token_expiry(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "profiles", "token_expires_at", "TEXT")
+}
+
+/// v12: preferências gerais do app (rede etc.), uma linha JSON por chave
+fn settings(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )
+}
+
+/// v13: sessões lançadas à mão e segundos já enviados ao rastreador, para lançar ajustes
+fn session_editing(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "sessions", "manual", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column(conn, "sessions", "synced_seconds", "INTEGER NOT NULL DEFAULT 0")?;
+    conn.execute(
+        "UPDATE sessions
+         SET synced_seconds = COALESCE((SELECT SUM(end_time - start_time) FROM session_intervals i
+                                        WHERE i.session_id = sessions.id AND i.end_time IS NOT NULL), 0)
+         WHERE sync_status = 'sincronizado'",
+        [],
+    )?;
+    Ok(())
+}
+
+/// v14: intervalos em que o usuário estava ausente, aguardando decisão
+fn interval_idle(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "session_intervals", "idle", "INTEGER NOT NULL DEFAULT 0")
+}
+
+/// v15: sessões pausadas pelo bloqueio de tela ou suspensão, que podem voltar sozinhas
+fn session_auto_paused(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "sessions", "auto_paused", "INTEGER NOT NULL DEFAULT 0")
+}
+
+/// v16: lançamentos em envio, que não podem perder a sessão no meio do caminho
+fn outbox_sending(conn: &Connection) -> rusqlite::Result<()> {
+    add_column(conn, "sync_outbox", "sending_at", "INTEGER")
+}
+
+/// v17: o ETag só vale para listagens de uma página; os gravados para as maiores são descartados
+fn cache_single_page_etags(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("UPDATE gitlab_cache SET etag=NULL", []).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Banco como era criado antes das migrações, com uma sessão finalizada
+    fn baseline_fixture() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        baseline(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO config (url, token, labels, gitlab_user, gitlab_user_id)
+             VALUES ('https://gitlab.example.com', 'tok', 'Dev,Review', 'dev', 7);
+             INSERT INTO sessions (group_id, project_id, issue_id, label, status)
+             VALUES (1, 2, 3, 'Dev', 'finalizado');
+             INSERT INTO session_intervals (session_id, start_time, end_time)
+             VALUES (1, 1000, 1600);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn upgrades_baseline_database() {
+        let mut conn = baseline_fixture();
+        assert_eq!(current_version(&conn).unwrap(), 0);
+
+        assert_eq!(migrate(&mut conn, None).unwrap(), latest_version());
+
+        let sessions = columns(&conn, "sessions");
+        for column in ["sync_status", "issue_iid", "issue_title", "issue_web_url", "synced_seconds"] {
+            assert!(sessions.contains(&column.to_string()), "faltando {}", column);
+        }
+        let intervals = columns(&conn, "session_intervals");
+        assert!(intervals.contains(&"heartbeat_at".to_string()));
+        assert!(intervals.contains(&"recovered".to_string()));
+        assert!(columns(&conn, "config").contains(&"page_limit".to_string()));
+
+        let (label, seconds): (String, i64) = conn
+            .query_row(
+                "SELECT s.label, i.end_time - i.start_time
+                 FROM sessions s JOIN session_intervals i ON i.session_id = s.id",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(label, "Dev");
+        assert_eq!(seconds, 600);
+    }
+
+    #[test]
+    fn moves_config_into_first_profile() {
+        let mut conn = baseline_fixture();
+        migrate(&mut conn, None).unwrap();
+
+        let (profile_id, url, user_id): (i64, String, i64) = conn
+            .query_row("SELECT id, url, gitlab_user_id FROM profiles", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((url.as_str(), user_id), ("https://gitlab.example.com", 7));
+        let session_profile: i64 = conn
+            .query_row("SELECT profile_id FROM sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(session_profile, profile_id);
+    }
+
+    #[test]
+    fn migrate_is_idempotent() {
+        let mut conn = baseline_fixture();
+        migrate(&mut conn, None).unwrap();
+        assert_eq!(migrate(&mut conn, None).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn tolerates_columns_added_before_versioning() {
+        let mut conn = baseline_fixture();
+        conn.execute("ALTER TABLE sessions ADD COLUMN issue_iid INTEGER", [])
+            .unwrap();
+
+        assert_eq!(migrate(&mut conn, None).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn creates_fresh_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(migrate(&mut conn, None).unwrap(), latest_version());
+        assert!(columns(&conn, "sync_outbox").contains(&"next_attempt_at".to_string()));
+        assert!(columns(&conn, "gitlab_cache").contains(&"etag".to_string()));
+    }
+
+    #[test]
+    fn backs_up_before_migrating() {
+        let dir = std::env::temp_dir().join(format!("ttd-migrations-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let backup_path = dir.join("config.db.v0.bak");
+
+        let mut conn = baseline_fixture();
+        migrate(&mut conn, Some(&backup_path)).unwrap();
+
+        let backup = Connection::open(&backup_path).unwrap();
+        assert_eq!(current_version(&backup).unwrap(), 0);
+        let sessions: i64 = backup
+            .query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sessions, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
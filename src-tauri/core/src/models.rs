@@ -0,0 +1,171 @@
+//! Modelos devolvidos à interface, no formato da API do GitLab;
+//! os outros backends convertem as respostas deles para estes tipos.
+
+use serde::{Deserialize, Serialize};
+
+/// Usuário (`/user`, autores e responsáveis)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    pub name: String,
+    #[serde(default)]
+    pub web_url: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+}
+
+/// Grupo (`/groups`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Group {
+    pub id: i64,
+    pub name: String,
+    pub full_path: String,
+    pub full_name: String,
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    #[serde(default)]
+    pub web_url: Option<String>,
+}
+
+/// Projeto (`/groups/:id/projects`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    pub name_with_namespace: String,
+    pub path_with_namespace: String,
+    #[serde(default)]
+    pub web_url: Option<String>,
+}
+
+/// Milestone resumido, como vem dentro da issue
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Milestone {
+    pub id: i64,
+    pub title: String,
+}
+
+/// Issue (`/projects/:id/issues`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Issue {
+    pub id: i64,
+    pub iid: i64,
+    pub project_id: i64,
+    pub title: String,
+    pub state: String,
+    pub web_url: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub milestone: Option<Milestone>,
+    #[serde(default)]
+    pub assignees: Vec<User>,
+    #[serde(default)]
+    pub time_stats: Option<TimeStats>,
+}
+
+/// Tempo estimado e gasto na issue (segundos)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TimeStats {
+    pub time_estimate: i64,
+    pub total_time_spent: i64,
+    #[serde(default)]
+    pub human_time_estimate: Option<String>,
+    #[serde(default)]
+    pub human_total_time_spent: Option<String>,
+}
+
+/// Comentário da issue (`/projects/:id/issues/:iid/notes`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Note {
+    pub id: i64,
+    pub body: String,
+    pub author: User,
+    pub created_at: String,
+    #[serde(default)]
+    pub system: bool,
+}
+
+/// Token em uso (`/personal_access_tokens/self`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TokenInfo {
+    pub name: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Data (`AAAA-MM-DD`) em que o token expira; `None` se não expira
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    #[serde(default)]
+    pub active: bool,
+}
+
+/// Dados da issue guardados na sessão
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IssueRef {
+    pub id: i64,
+    pub iid: i64,
+    pub title: String,
+    pub web_url: String,
+}
+
+impl From<&Issue> for IssueRef {
+    fn from(issue: &Issue) -> Self {
+        IssueRef {
+            id: issue.id,
+            iid: issue.iid,
+            title: issue.title.clone(),
+            web_url: issue.web_url.clone(),
+        }
+    }
+}
+
+/// Filtros da listagem de issues; cada backend traduz para os parâmetros da sua API
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct IssueFilter {
+    /// Texto buscado no título e na descrição
+    pub search: Option<String>,
+    /// Labels separadas por vírgula
+    pub labels: Option<String>,
+    /// Título do milestone
+    pub milestone: Option<String>,
+    /// Usuário responsável (só GitLab)
+    pub assignee_id: Option<i64>,
+    /// `opened`, `closed` ou `all`
+    pub state: Option<String>,
+    /// `created_by_me`, `assigned_to_me` ou `all` (só GitLab)
+    pub scope: Option<String>,
+    /// Apenas issues atribuídas ao usuário do token
+    pub assigned_to_me: bool,
+}
+
+impl IssueFilter {
+    /// Filtro padrão: issues abertas atribuídas ao usuário configurado
+    pub fn assigned_to_me() -> Self {
+        IssueFilter {
+            state: Some("opened".into()),
+            assigned_to_me: true,
+            ..Default::default()
+        }
+    }
+
+    /// Pares (chave, valor) dos filtros de texto preenchidos
+    pub(crate) fn text_params(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("search", &self.search),
+            ("labels", &self.labels),
+            ("milestone", &self.milestone),
+            ("state", &self.state),
+            ("scope", &self.scope),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(|v| (key, v))
+        })
+    }
+}
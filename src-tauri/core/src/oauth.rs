@@ -0,0 +1,352 @@
+//! Login no GitLab por OAuth2 (authorization code + PKCE) com redirecionamento
+//! para um servidor local de uso único, e renovação dos tokens quando expiram.
+
+use crate::error::{Error, Result};
+use crate::tracker::{Clock, SystemClock};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+/// Escopo pedido: `api` cobre leitura das issues e o lançamento de tempo
+const SCOPE: &str = "api";
+
+/// Tempo máximo esperando o usuário concluir o login no navegador
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Margem para renovar antes de o access token expirar de fato
+const EXPIRY_MARGIN: i64 = 60;
+
+/// Tokens devolvidos pelo servidor; guardados como JSON no `SecretStore`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OAuthTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Unix timestamp em que o access token expira (`None` se o servidor não informou)
+    pub expires_at: Option<i64>,
+}
+
+impl OAuthTokens {
+    pub fn to_secret(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| Error::Internal(e.to_string()))
+    }
+
+    pub fn from_secret(secret: &str) -> Result<Self> {
+        serde_json::from_str(secret)
+            .map_err(|_| Error::Auth("Sessão OAuth inválida; entre novamente".into()))
+    }
+
+    fn expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|at| at - EXPIRY_MARGIN <= now)
+    }
+}
+
+/// Resposta de `POST /oauth/token`
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: Option<i64>,
+    created_at: Option<i64>,
+}
+
+impl From<TokenResponse> for OAuthTokens {
+    fn from(resp: TokenResponse) -> Self {
+        let created_at = resp.created_at.unwrap_or_else(|| SystemClock.now());
+        OAuthTokens {
+            access_token: resp.access_token,
+            refresh_token: resp.refresh_token,
+            expires_at: resp.expires_in.map(|secs| created_at + secs),
+        }
+    }
+}
+
+/// Pedido de autorização em andamento: URL a abrir no navegador e o que é preciso para concluí-lo
+pub struct AuthRequest {
+    pub url: Url,
+    pub state: String,
+    pub redirect_uri: String,
+    verifier: String,
+}
+
+/// Monta a URL de `/oauth/authorize` com um `code_verifier` e um `state` novos
+pub fn authorization(base_url: &str, client_id: &str, redirect_uri: &str) -> Result<AuthRequest> {
+    let verifier = random_string(32);
+    let state = random_string(16);
+    let mut url = endpoint(base_url, "/oauth/authorize")?;
+    url.query_pairs_mut()
+        .append_pair("client_id", client_id)
+        .append_pair("redirect_uri", redirect_uri)
+        .append_pair("response_type", "code")
+        .append_pair("scope", SCOPE)
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &challenge(&verifier))
+        .append_pair("code_challenge_method", "S256");
+    Ok(AuthRequest { url, state, redirect_uri: redirect_uri.to_string(), verifier })
+}
+
+/// Troca o `code` recebido no redirecionamento pelos tokens
+pub async fn exchange_code(
+    http: &reqwest::Client,
+    base_url: &str,
+    client_id: &str,
+    code: &str,
+    request: &AuthRequest,
+) -> Result<OAuthTokens> {
+    let form = [
+        ("grant_type", "authorization_code"),
+        ("client_id", client_id),
+        ("code", code),
+        ("redirect_uri", &request.redirect_uri),
+        ("code_verifier", &request.verifier),
+    ];
+    token_request(http, base_url, &form, "Autorização recusada pelo servidor").await
+}
+
+/// Pede um access token novo com o refresh token
+pub async fn refresh(
+    http: &reqwest::Client,
+    base_url: &str,
+    client_id: &str,
+    refresh_token: &str,
+) -> Result<OAuthTokens> {
+    let form = [
+        ("grant_type", "refresh_token"),
+        ("client_id", client_id),
+        ("refresh_token", refresh_token),
+    ];
+    token_request(http, base_url, &form, "Sessão OAuth expirada; entre novamente").await
+}
+
+async fn token_request(
+    http: &reqwest::Client,
+    base_url: &str,
+    form: &[(&str, &str)],
+    rejected: &str,
+) -> Result<OAuthTokens> {
+    let resp = http.post(endpoint(base_url, "/oauth/token")?).form(form).send().await?;
+    // `invalid_grant` e afins chegam como 400/401
+    if matches!(resp.status(), StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED) {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(Error::Auth(format!("{} ({})", rejected, body)));
+    }
+    let resp = crate::http::check(resp).await?;
+    Ok(resp.json::<TokenResponse>().await?.into())
+}
+
+/// Chamado com os tokens renovados, para guardá-los
+pub type PersistTokens = Box<dyn Fn(&OAuthTokens) -> Result<()> + Send + Sync>;
+
+/// Tokens de um perfil em uso pelo cliente do GitLab.
+///
+/// Renova sozinho quando o access token expira ou a API responde 401. O GitLab troca
+/// o refresh token a cada uso, então a sessão precisa ser uma só por perfil (em um
+/// `Arc`): o lock garante uma só renovação com várias chamadas falhando ao mesmo tempo.
+pub struct OAuthSession {
+    http: reqwest::Client,
+    base_url: String,
+    client_id: String,
+    tokens: Mutex<OAuthTokens>,
+    persist: PersistTokens,
+}
+
+impl OAuthSession {
+    pub fn new(
+        http: &reqwest::Client,
+        base_url: &str,
+        client_id: &str,
+        tokens: OAuthTokens,
+        persist: PersistTokens,
+    ) -> Self {
+        OAuthSession {
+            http: http.clone(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client_id: client_id.to_string(),
+            tokens: Mutex::new(tokens),
+            persist,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Access token válido, renovando antes se já expirou
+    pub async fn access_token(&self) -> Result<String> {
+        let mut tokens = self.tokens.lock().await;
+        if tokens.expired(SystemClock.now()) {
+            self.renew(&mut tokens).await?;
+        }
+        Ok(tokens.access_token.clone())
+    }
+
+    /// Renova depois que `rejected` recebeu 401; se outra chamada já renovou, só devolve o novo
+    pub async fn refresh_after(&self, rejected: &str) -> Result<String> {
+        let mut tokens = self.tokens.lock().await;
+        if tokens.access_token == rejected {
+            self.renew(&mut tokens).await?;
+        }
+        Ok(tokens.access_token.clone())
+    }
+
+    async fn renew(&self, tokens: &mut OAuthTokens) -> Result<()> {
+        let fresh = refresh(&self.http, &self.base_url, &self.client_id, &tokens.refresh_token).await?;
+        (self.persist)(&fresh)?;
+        *tokens = fresh;
+        Ok(())
+    }
+}
+
+/// Servidor local que recebe o redirecionamento do navegador (`http://127.0.0.1:<porta>/callback`)
+pub struct Loopback {
+    listener: TcpListener,
+}
+
+impl Loopback {
+    /// Escuta numa porta livre de 127.0.0.1
+    pub async fn bind() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| Error::Internal(format!("porta local para o login: {}", e)))?;
+        Ok(Loopback { listener })
+    }
+
+    pub fn redirect_uri(&self) -> String {
+        let port = self.listener.local_addr().map(|addr| addr.port()).unwrap_or(0);
+        format!("http://127.0.0.1:{}/callback", port)
+    }
+
+    /// Espera o redirecionamento com o `state` do pedido e devolve o `code`
+    pub async fn wait_code(self, state: &str) -> Result<String> {
+        tokio::time::timeout(LOGIN_TIMEOUT, self.accept_callback(state))
+            .await
+            .map_err(|_| Error::Auth("Tempo esgotado esperando o login no navegador".into()))?
+    }
+
+    /// Redirecionamentos com outro `state` (aba antiga, outro site) recebem erro e são
+    /// ignorados: só o do pedido atual conclui ou recusa o login
+    async fn accept_callback(&self, state: &str) -> Result<String> {
+        loop {
+            let (mut stream, _) = self
+                .listener
+                .accept()
+                .await
+                .map_err(|e| Error::Internal(format!("login local: {}", e)))?;
+            // outras requisições do navegador (ex.: favicon) recebem 404
+            let Some(url) = read_request_url(&mut stream).await.filter(|url| url.path() == "/callback") else {
+                respond(&mut stream, "404 Not Found", "Não encontrado").await;
+                continue;
+            };
+
+            let param = |name: &str| {
+                url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned())
+            };
+            if param("state").as_deref() != Some(state) {
+                respond(&mut stream, "400 Bad Request", "Resposta de login inválida para este pedido.").await;
+                continue;
+            }
+            let result = if let Some(error) = param("error") {
+                let description = param("error_description").unwrap_or_default();
+                Err(Error::Auth(format!("Login recusado: {} {}", error, description).trim_end().to_string()))
+            } else {
+                param("code").ok_or_else(|| Error::Auth("Resposta de login sem código".into()))
+            };
+
+            let page = match &result {
+                Ok(_) => "Login concluído. Pode fechar esta janela e voltar ao TraceTimeDev.",
+                Err(_) => "Não foi possível concluir o login. Volte ao TraceTimeDev e tente de novo.",
+            };
+            respond(&mut stream, "200 OK", page).await;
+            return result;
+        }
+    }
+}
+
+/// Lê o cabeçalho da requisição e devolve a URL pedida
+async fn read_request_url(stream: &mut TcpStream) -> Option<Url> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") && buf.len() < 16 * 1024 {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    }
+    let head = String::from_utf8_lossy(&buf);
+    let target = head.lines().next()?.split_whitespace().nth(1)?;
+    Url::parse(&format!("http://127.0.0.1{}", target)).ok()
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, message: &str) {
+    let body = format!("<!doctype html><meta charset=\"utf-8\"><title>TraceTimeDev</title><p>{}</p>", message);
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+fn endpoint(base_url: &str, path: &str) -> Result<Url> {
+    Url::parse(&format!("{}{}", base_url.trim_end_matches('/'), path))
+        .map_err(|e| Error::InvalidState(format!("URL do GitLab inválida: {}", e)))
+}
+
+/// `code_challenge` do método S256: SHA-256 do verifier em base64url sem padding
+fn challenge(verifier: &str) -> String {
+    BASE64_URL.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+fn random_string(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buf);
+    BASE64_URL.encode(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge_matches_rfc_7636_example() {
+        assert_eq!(
+            challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn authorization_url_carries_pkce_and_state() {
+        let request = authorization("https://gitlab.example.com/", "app-id", "http://127.0.0.1:4000/callback").unwrap();
+        let query: std::collections::HashMap<_, _> = request.url.query_pairs().into_owned().collect();
+
+        assert_eq!(request.url.path(), "/oauth/authorize");
+        assert_eq!(query["client_id"], "app-id");
+        assert_eq!(query["state"], request.state);
+        assert_eq!(query["code_challenge"], challenge(&request.verifier));
+        assert_eq!(query["code_challenge_method"], "S256");
+        assert!(request.verifier.len() >= 43, "{}", request.verifier);
+    }
+
+    #[test]
+    fn expires_with_margin() {
+        let tokens = OAuthTokens { access_token: "a".into(), refresh_token: "r".into(), expires_at: Some(1000) };
+        assert!(!tokens.expired(900));
+        assert!(tokens.expired(950));
+        assert!(!OAuthTokens { expires_at: None, ..tokens }.expired(i64::MAX));
+    }
+}
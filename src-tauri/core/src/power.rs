@@ -0,0 +1,225 @@
+use crate::error::Result;
+use crate::state::TimerState;
+use crate::tracker::{Clock, Tracker};
+use rusqlite::params;
+use serde::Serialize;
+
+/// Eventos do sistema que afetam o timer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerEvent {
+    Lock,
+    Unlock,
+    /// Logo antes de suspender
+    Sleep,
+    /// Na volta da suspensão
+    Wake,
+    /// Desligamento do sistema ou saída do app
+    Shutdown,
+}
+
+/// Fonte dos eventos do sistema
+pub trait PowerSource: Send {
+    /// Espera o próximo evento; `None` quando a fonte não tem mais o que informar
+    fn next_event(&mut self) -> Option<PowerEvent>;
+}
+
+/// Para sistemas sem fonte conhecida: nenhum evento
+pub struct NoPowerSource;
+
+impl PowerSource for NoPowerSource {
+    fn next_event(&mut self) -> Option<PowerEvent> {
+        None
+    }
+}
+
+/// Fonte do sistema: logind e ScreenSaver pelo D-Bus no Linux (com a feature `power`), nenhuma nos demais
+pub fn system_source() -> Box<dyn PowerSource> {
+    #[cfg(all(feature = "power", target_os = "linux"))]
+    return Box::new(dbus::DbusPower::connect());
+    #[cfg(not(all(feature = "power", target_os = "linux")))]
+    Box::new(NoPowerSource)
+}
+
+/// O que um evento do sistema fez com a sessão
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "action", content = "session_id", rename_all = "lowercase")]
+pub enum PowerAction {
+    Paused(i64),
+    Resumed(i64),
+}
+
+impl<C: Clock> Tracker<'_, C> {
+    /// Pausa o timer rodando ao bloquear, suspender ou desligar.
+    ///
+    /// Com `auto_resume`, o desbloqueio retoma a sessão pausada pelo bloqueio ou
+    /// suspensão, desde que o usuário não tenha mexido nela depois.
+    pub fn handle_power(&self, event: PowerEvent, auto_resume: bool) -> Result<Option<PowerAction>> {
+        match (event, self.state()?) {
+            (PowerEvent::Lock | PowerEvent::Sleep | PowerEvent::Shutdown, TimerState::Running { session_id, .. }) => {
+                let tx = self.conn.unchecked_transaction()?;
+                self.pause(session_id)?;
+                // depois de desligar não faz sentido voltar sozinho
+                if event != PowerEvent::Shutdown {
+                    self.conn.execute("UPDATE sessions SET auto_paused=1 WHERE id=?1", params![session_id])?;
+                }
+                tx.commit()?;
+                Ok(Some(PowerAction::Paused(session_id)))
+            }
+            // ao acordar a tela ainda costuma estar bloqueada: só o desbloqueio retoma
+            (PowerEvent::Unlock, TimerState::Paused { session_id }) if auto_resume => {
+                let auto_paused: bool = self.conn.query_row(
+                    "SELECT auto_paused FROM sessions WHERE id=?1",
+                    params![session_id],
+                    |row| row.get(0),
+                )?;
+                if !auto_paused {
+                    return Ok(None);
+                }
+                self.resume(session_id)?;
+                Ok(Some(PowerAction::Resumed(session_id)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Sinais do logind (suspensão, desligamento, bloqueio da sessão) e do ScreenSaver do ambiente
+#[cfg(all(feature = "power", target_os = "linux"))]
+mod dbus {
+    use super::{PowerEvent, PowerSource};
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use zbus::blocking::{Connection, MessageIterator};
+    use zbus::message::Type;
+    use zbus::zvariant::{OwnedFd, OwnedObjectPath};
+    use zbus::{MatchRule, Message};
+
+    const LOGIN1: &str = "org.freedesktop.login1";
+    const LOGIN1_PATH: &str = "/org/freedesktop/login1";
+    const LOGIN1_MANAGER: &str = "org.freedesktop.login1.Manager";
+    /// O GNOME emite só pela própria interface; KDE e os demais, pela do freedesktop
+    const SCREENSAVERS: [&str; 2] = ["org.freedesktop.ScreenSaver", "org.gnome.ScreenSaver"];
+
+    pub struct DbusPower {
+        events: Receiver<PowerEvent>,
+        system: Option<Connection>,
+        /// Segura a suspensão até o timer ser pausado
+        inhibitor: Option<OwnedFd>,
+        release: bool,
+    }
+
+    impl DbusPower {
+        pub fn connect() -> Self {
+            let (tx, events) = mpsc::channel();
+            let system = Connection::system().ok();
+            if let Some(system) = &system {
+                let manager = || MatchRule::builder().msg_type(Type::Signal).sender(LOGIN1)?.interface(LOGIN1_MANAGER);
+                if let Ok(rule) = manager().and_then(|r| r.member("PrepareForSleep")).map(|r| r.build()) {
+                    listen(system, rule, tx.clone(), |msg| {
+                        let sleeping: bool = msg.body().deserialize().ok()?;
+                        Some(if sleeping { PowerEvent::Sleep } else { PowerEvent::Wake })
+                    });
+                }
+                if let Ok(rule) = manager().and_then(|r| r.member("PrepareForShutdown")).map(|r| r.build()) {
+                    listen(system, rule, tx.clone(), |msg| {
+                        msg.body().deserialize::<bool>().ok()?.then_some(PowerEvent::Shutdown)
+                    });
+                }
+                if let Some(rule) = own_session(system).and_then(|path| {
+                    MatchRule::builder()
+                        .msg_type(Type::Signal)
+                        .sender(LOGIN1)
+                        .and_then(|r| r.interface("org.freedesktop.login1.Session"))
+                        .and_then(|r| r.path(path))
+                        .map(|r| r.build())
+                        .ok()
+                }) {
+                    listen(system, rule, tx.clone(), |msg| match msg.header().member()?.as_str() {
+                        "Lock" => Some(PowerEvent::Lock),
+                        "Unlock" => Some(PowerEvent::Unlock),
+                        _ => None,
+                    });
+                }
+            }
+            // GNOME e KDE avisam o bloqueio pelo ScreenSaver da sessão, não pelo logind
+            if let Ok(session) = Connection::session() {
+                for interface in SCREENSAVERS {
+                    let rule = MatchRule::builder()
+                        .msg_type(Type::Signal)
+                        .interface(interface)
+                        .and_then(|r| r.member("ActiveChanged"))
+                        .map(|r| r.build());
+                    if let Ok(rule) = rule {
+                        listen(&session, rule, tx.clone(), |msg| {
+                            let active: bool = msg.body().deserialize().ok()?;
+                            Some(if active { PowerEvent::Lock } else { PowerEvent::Unlock })
+                        });
+                    }
+                }
+            }
+            let inhibitor = system.as_ref().and_then(inhibit);
+            DbusPower { events, system, inhibitor, release: false }
+        }
+    }
+
+    impl PowerSource for DbusPower {
+        /// A trava da suspensão só é solta quando o evento anterior já foi tratado
+        fn next_event(&mut self) -> Option<PowerEvent> {
+            if self.release {
+                self.inhibitor = None;
+                self.release = false;
+            }
+            let event = self.events.recv().ok()?;
+            match event {
+                PowerEvent::Sleep | PowerEvent::Shutdown => self.release = true,
+                PowerEvent::Wake if self.inhibitor.is_none() => {
+                    self.inhibitor = self.system.as_ref().and_then(inhibit);
+                }
+                _ => {}
+            }
+            Some(event)
+        }
+    }
+
+    /// Repassa os sinais da regra como eventos até o canal ser fechado
+    fn listen(
+        conn: &Connection,
+        rule: MatchRule<'static>,
+        tx: Sender<PowerEvent>,
+        map: fn(&Message) -> Option<PowerEvent>,
+    ) {
+        let Ok(messages) = MessageIterator::for_match_rule(rule, conn, Some(16)) else {
+            return;
+        };
+        std::thread::spawn(move || {
+            for msg in messages.flatten() {
+                if let Some(event) = map(&msg) {
+                    if tx.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Sessão do logind em que o app roda (os sinais de bloqueio vêm nela)
+    fn own_session(system: &Connection) -> Option<OwnedObjectPath> {
+        let reply = system
+            .call_method(Some(LOGIN1), LOGIN1_PATH, Some(LOGIN1_MANAGER), "GetSessionByPID", &(std::process::id()))
+            .ok()?;
+        reply.body().deserialize().ok()
+    }
+
+    /// Pede ao logind para esperar o timer pausar antes de suspender ou desligar
+    fn inhibit(system: &Connection) -> Option<OwnedFd> {
+        let reply = system
+            .call_method(
+                Some(LOGIN1),
+                LOGIN1_PATH,
+                Some(LOGIN1_MANAGER),
+                "Inhibit",
+                &("sleep:shutdown", "TraceTimeDev", "Pausar o timer", "delay"),
+            )
+            .ok()?;
+        reply.body().deserialize().ok()
+    }
+}
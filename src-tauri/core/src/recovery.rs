@@ -0,0 +1,89 @@
+use crate::error::Result;
+use crate::sync;
+use crate::tracker::{Clock, Tracker};
+use rusqlite::params;
+use serde::Serialize;
+
+/// Tempo recuperado de intervalos que ficaram abertos (crash, desligamento)
+#[derive(Serialize, Debug)]
+pub struct Recovery {
+    pub session_id: i64,
+    pub seconds: i64,
+    pub minutes: i64,
+}
+
+impl<C: Clock> Tracker<'_, C> {
+    /// Fecha intervalos abertos no último heartbeat, em vez de contar o tempo com o app fechado.
+    ///
+    /// Os intervalos fechados ficam marcados como `recovered` até o usuário decidir
+    /// se mantém ou descarta o tempo (`resolve_recovery`).
+    pub fn close_orphaned_intervals(&self) -> Result<usize> {
+        let closed = self.conn.execute(
+            "UPDATE session_intervals
+             SET end_time = MAX(start_time, COALESCE(heartbeat_at, start_time)), recovered = 1
+             WHERE end_time IS NULL",
+            [],
+        )?;
+
+        if closed > 0 {
+            self.conn.execute(
+                "UPDATE sessions SET status='pause', updated_at=?1
+                 WHERE status='runner'
+                   AND id IN (SELECT session_id FROM session_intervals WHERE recovered = 1)",
+                params![self.now()],
+            )?;
+        }
+        Ok(closed)
+    }
+
+    /// Registra que o intervalo aberto ainda está vivo
+    pub fn heartbeat(&self) -> Result<()> {
+        self.conn.execute(
+            "UPDATE session_intervals SET heartbeat_at = ?1
+             WHERE end_time IS NULL",
+            params![self.now()],
+        )?;
+        Ok(())
+    }
+
+    /// Tempo recuperado aguardando decisão do usuário
+    pub fn pending_recovery(&self) -> Result<Option<Recovery>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT session_id, SUM(end_time - start_time)
+             FROM session_intervals
+             WHERE recovered = 1
+             GROUP BY session_id
+             ORDER BY MAX(end_time) DESC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query([])?;
+        if let Some(row) = rows.next()? {
+            let seconds: i64 = row.get::<_, Option<i64>>(1)?.unwrap_or(0);
+            Ok(Some(Recovery {
+                session_id: row.get(0)?,
+                seconds,
+                minutes: seconds / 60,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Mantém ou descarta o tempo recuperado da sessão.
+    ///
+    /// Descartar apaga o intervalo inteiro que ficou aberto, não só o fim depois do
+    /// último heartbeat. Sessão já finalizada tem o lançamento refeito com a decisão.
+    pub fn resolve_recovery(&self, session_id: i64, keep: bool) -> Result<()> {
+        self.check_not_sending(session_id)?;
+        let sql = if keep {
+            "UPDATE session_intervals SET recovered = 0 WHERE session_id=?1 AND recovered = 1"
+        } else {
+            "DELETE FROM session_intervals WHERE session_id=?1 AND recovered = 1"
+        };
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(sql, params![session_id])?;
+        sync::enqueue_session(self.conn, session_id)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+//! Guarda dos tokens fora do banco: chaveiro do sistema (Secret Service no Linux)
+//! ou, sem ele, um arquivo cifrado com chave derivada de uma senha ou da máquina.
+
+use crate::error::{Error, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Nome do serviço no chaveiro
+#[cfg(feature = "keyring")]
+const SERVICE: &str = "tracetimedev";
+
+/// Tamanho do nonce do ChaCha20-Poly1305
+const NONCE_LEN: usize = 12;
+
+/// Onde os tokens ficam guardados
+pub trait SecretStore: Send + Sync {
+    fn get(&self, account: &str) -> Result<Option<String>>;
+    fn set(&self, account: &str, secret: &str) -> Result<()>;
+    fn delete(&self, account: &str) -> Result<()>;
+}
+
+impl<S: SecretStore + ?Sized> SecretStore for Box<S> {
+    fn get(&self, account: &str) -> Result<Option<String>> {
+        (**self).get(account)
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<()> {
+        (**self).set(account, secret)
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        (**self).delete(account)
+    }
+}
+
+/// Conta do token de um perfil
+pub fn account(profile_id: i64) -> String {
+    format!("profile-{}", profile_id)
+}
+
+/// Forma mascarada do token para a interface: prefixo (`glpat-`) e os 4 últimos caracteres
+pub fn mask(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() <= 8 {
+        return "••••".into();
+    }
+    let prefix = token.find('-').filter(|i| *i < 8).map_or("", |i| &token[..=i]);
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}••••{}", prefix, suffix)
+}
+
+/// Chaveiro do sistema (Secret Service, Keychain ou Credential Manager)
+#[cfg(feature = "keyring")]
+pub struct Keyring;
+
+#[cfg(feature = "keyring")]
+impl Keyring {
+    /// O chaveiro, se houver um serviço respondendo
+    pub fn probe() -> Option<Self> {
+        let entry = keyring::Entry::new(SERVICE, "probe").ok()?;
+        match entry.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Some(Keyring),
+            Err(_) => None,
+        }
+    }
+
+    fn entry(account: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(SERVICE, account).map_err(keyring_error)
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl SecretStore for Keyring {
+    fn get(&self, account: &str) -> Result<Option<String>> {
+        match Self::entry(account)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(keyring_error(e)),
+        }
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<()> {
+        Self::entry(account)?.set_password(secret).map_err(keyring_error)
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        match Self::entry(account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(keyring_error(e)),
+        }
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_error(e: keyring::Error) -> Error {
+    Error::Internal(format!("chaveiro do sistema: {}", e))
+}
+
+/// Origem da chave do arquivo cifrado
+pub enum KeySource {
+    /// Senha escolhida pelo usuário
+    Passphrase(String),
+    /// Identificador da máquina (o arquivo só abre nela)
+    Machine,
+}
+
+/// Conteúdo do arquivo: sal da derivação e os tokens cifrados (nonce + texto cifrado)
+#[derive(Serialize, Deserialize, Default)]
+struct SecretsFile {
+    salt: String,
+    entries: HashMap<String, String>,
+}
+
+/// Tokens cifrados com ChaCha20-Poly1305 em um arquivo JSON; a chave sai do Argon2
+pub struct EncryptedFile {
+    path: PathBuf,
+    cipher: ChaCha20Poly1305,
+    file: Mutex<SecretsFile>,
+}
+
+impl EncryptedFile {
+    /// Abre (ou cria) o arquivo e deriva a chave
+    pub fn open(path: &Path, source: KeySource) -> Result<Self> {
+        let mut file: SecretsFile = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| Error::Internal(format!("arquivo de tokens ilegível: {}", e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => SecretsFile::default(),
+            Err(e) => return Err(Error::Internal(format!("arquivo de tokens: {}", e))),
+        };
+        if file.salt.is_empty() {
+            file.salt = BASE64.encode(ChaCha20Poly1305::generate_nonce(&mut OsRng));
+        }
+
+        let secret = match source {
+            KeySource::Passphrase(passphrase) => passphrase,
+            KeySource::Machine => machine_uid::get()
+                .map_err(|e| Error::Internal(format!("identificador da máquina indisponível: {}", e)))?,
+        };
+        let salt = BASE64.decode(&file.salt).map_err(|e| Error::Internal(e.to_string()))?;
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(secret.as_bytes(), &salt, &mut key)
+            .map_err(|e| Error::Internal(format!("derivação da chave: {}", e)))?;
+
+        Ok(EncryptedFile {
+            path: path.to_path_buf(),
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+            file: Mutex::new(file),
+        })
+    }
+
+    fn write(&self, file: &SecretsFile) -> Result<()> {
+        let content = serde_json::to_string_pretty(file).map_err(|e| Error::Internal(e.to_string()))?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, content)
+            .and_then(|_| restrict_permissions(&tmp))
+            .and_then(|_| fs::rename(&tmp, &self.path))
+            .map_err(|e| Error::Internal(format!("arquivo de tokens: {}", e)))
+    }
+}
+
+impl SecretStore for EncryptedFile {
+    fn get(&self, account: &str) -> Result<Option<String>> {
+        let file = self.file.lock()?;
+        let Some(sealed) = file.entries.get(account) else {
+            return Ok(None);
+        };
+        let sealed = BASE64.decode(sealed).map_err(|e| Error::Internal(e.to_string()))?;
+        if sealed.len() < NONCE_LEN {
+            return Err(Error::Internal("token cifrado corrompido".into()));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plain = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| Error::Auth("Não foi possível decifrar o token; a senha mudou?".into()))?;
+        String::from_utf8(plain).map(Some).map_err(|e| Error::Internal(e.to_string()))
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<()> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, secret.as_bytes())
+            .map_err(|e| Error::Internal(format!("cifragem do token: {}", e)))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+
+        let mut file = self.file.lock()?;
+        file.entries.insert(account.to_string(), BASE64.encode(sealed));
+        self.write(&file)
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        let mut file = self.file.lock()?;
+        if file.entries.remove(account).is_some() {
+            self.write(&file)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Tokens só em memória (testes)
+#[derive(Default)]
+pub struct MemoryStore(Mutex<HashMap<String, String>>);
+
+impl SecretStore for MemoryStore {
+    fn get(&self, account: &str) -> Result<Option<String>> {
+        Ok(self.0.lock()?.get(account).cloned())
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<()> {
+        self.0.lock()?.insert(account.to_string(), secret.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        self.0.lock()?.remove(account);
+        Ok(())
+    }
+}
+
+/// Guarda com os tokens já lidos em memória.
+///
+/// O chaveiro não pode ser consultado de dentro do runtime assíncrono, então os
+/// tokens são lidos uma vez (na inicialização) e depois servidos daqui.
+pub struct Cached<S> {
+    store: S,
+    cache: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl<S: SecretStore> Cached<S> {
+    pub fn new(store: S) -> Self {
+        Cached { store, cache: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl<S: SecretStore> SecretStore for Cached<S> {
+    fn get(&self, account: &str) -> Result<Option<String>> {
+        if let Some(secret) = self.cache.lock()?.get(account) {
+            return Ok(secret.clone());
+        }
+        let secret = self.store.get(account)?;
+        self.cache.lock()?.insert(account.to_string(), secret.clone());
+        Ok(secret)
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<()> {
+        self.store.set(account, secret)?;
+        self.cache.lock()?.insert(account.to_string(), Some(secret.to_string()));
+        Ok(())
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        self.store.delete(account)?;
+        self.cache.lock()?.insert(account.to_string(), None);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ttd-secrets-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn masks_all_but_prefix_and_tail() {
+        assert_eq!(mask("glpat-abcdefghijklmnop"), "glpat-••••mnop");
+        assert_eq!(mask("0123456789abcdef"), "••••cdef");
+        assert_eq!(mask("curto"), "••••");
+    }
+
+    #[test]
+    fn encrypted_file_round_trip() {
+        let path = temp_file("round-trip.json");
+        let store = EncryptedFile::open(&path, KeySource::Passphrase("senha".into())).unwrap();
+        store.set(&account(1), "glpat-segredo").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("glpat-segredo"));
+
+        let reopened = EncryptedFile::open(&path, KeySource::Passphrase("senha".into())).unwrap();
+        assert_eq!(reopened.get(&account(1)).unwrap().as_deref(), Some("glpat-segredo"));
+        reopened.delete(&account(1)).unwrap();
+        assert_eq!(reopened.get(&account(1)).unwrap(), None);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn wrong_passphrase_cannot_decrypt() {
+        let path = temp_file("wrong-passphrase.json");
+        EncryptedFile::open(&path, KeySource::Passphrase("certa".into()))
+            .unwrap()
+            .set(&account(1), "glpat-segredo")
+            .unwrap();
+
+        let store = EncryptedFile::open(&path, KeySource::Passphrase("errada".into())).unwrap();
+        assert!(matches!(store.get(&account(1)), Err(Error::Auth(_))));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
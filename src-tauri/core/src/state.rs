@@ -0,0 +1,161 @@
+use crate::error::{Error, Result};
+use crate::tracker::{Clock, Tracker};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Estado do timer, sempre lido de `sessions.status` e do intervalo aberto.
+///
+/// É a única fonte para decidir se uma ação é válida: não há cópia em memória
+/// que possa divergir do banco depois de reiniciar o app.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum TimerState {
+    Idle,
+    Running { session_id: i64, interval_id: i64 },
+    Paused { session_id: i64 },
+}
+
+/// Ações do usuário sobre o timer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    Start,
+    Pause,
+    Resume,
+    Stop,
+}
+
+/// O que acontece com a sessão atual ao trocar de tarefa
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SwitchAction {
+    /// Finaliza e coloca na fila de envio
+    #[default]
+    Stop,
+    /// Deixa pausada para retomar depois
+    Pause,
+}
+
+/// Minutos sem teclado e mouse até separar o tempo ausente
+const DEFAULT_IDLE_MINUTES: i64 = 15;
+
+/// Preferências do timer
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TimerSettings {
+    pub switch_action: SwitchAction,
+    /// Minutos ausente até perguntar o que fazer com o tempo (0 desliga)
+    pub idle_minutes: i64,
+    /// Retoma ao desbloquear a tela a sessão pausada pelo bloqueio ou suspensão
+    pub auto_resume: bool,
+}
+
+impl Default for TimerSettings {
+    fn default() -> Self {
+        TimerSettings {
+            switch_action: SwitchAction::default(),
+            idle_minutes: DEFAULT_IDLE_MINUTES,
+            auto_resume: false,
+        }
+    }
+}
+
+impl TimerState {
+    pub fn session_id(&self) -> Option<i64> {
+        match self {
+            TimerState::Idle => None,
+            TimerState::Running { session_id, .. } | TimerState::Paused { session_id } => Some(*session_id),
+        }
+    }
+
+    /// Valor correspondente em `sessions.status` (`idle` quando não há sessão aberta)
+    pub fn status(&self) -> &'static str {
+        match self {
+            TimerState::Idle => "idle",
+            TimerState::Running { .. } => "runner",
+            TimerState::Paused { .. } => "pause",
+        }
+    }
+
+    /// Recusa a transição que não parte deste estado (ex.: pausar sessão finalizada)
+    pub fn check(&self, transition: Transition) -> Result<()> {
+        let refused = match (self, transition) {
+            (TimerState::Running { .. }, Transition::Start) => "Já existe um timer rodando",
+            (TimerState::Running { .. }, Transition::Resume) => "A sessão já está rodando",
+            (TimerState::Paused { .. }, Transition::Pause) => "A sessão já está pausada",
+            (TimerState::Idle, Transition::Pause | Transition::Resume | Transition::Stop) => {
+                "A sessão já foi finalizada"
+            }
+            _ => return Ok(()),
+        };
+        Err(Error::InvalidState(refused.into()))
+    }
+}
+
+impl<C: Clock> Tracker<'_, C> {
+    /// Estado atual do app: a sessão rodando ou, se nenhuma, a pausada mais recente
+    pub fn state(&self) -> Result<TimerState> {
+        let current: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT id FROM sessions
+                 WHERE status IN ('runner', 'pause')
+                 ORDER BY status = 'runner' DESC, updated_at DESC, id DESC
+                 LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match current {
+            Some(session_id) => self.session_state(session_id),
+            None => Ok(TimerState::Idle),
+        }
+    }
+
+    /// Estado de uma sessão; finalizada conta como `Idle`
+    pub fn session_state(&self, session_id: i64) -> Result<TimerState> {
+        let (status, interval_id): (Option<String>, Option<i64>) = self
+            .conn
+            .query_row(
+                "SELECT status, (SELECT MAX(id) FROM session_intervals
+                                 WHERE session_id = sessions.id AND end_time IS NULL)
+                 FROM sessions WHERE id=?1",
+                params![session_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .ok_or_else(|| Error::NotFound(format!("Sessão {} não encontrada", session_id)))?;
+
+        Ok(match (status.as_deref(), interval_id) {
+            (Some("runner"), Some(interval_id)) => TimerState::Running { session_id, interval_id },
+            // rodando sem intervalo aberto só acontece em bancos antigos: trata como pausada
+            (Some("runner" | "pause"), _) => TimerState::Paused { session_id },
+            _ => TimerState::Idle,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_valid_transitions_pass() {
+        let running = TimerState::Running { session_id: 1, interval_id: 2 };
+        let paused = TimerState::Paused { session_id: 1 };
+
+        assert!(TimerState::Idle.check(Transition::Start).is_ok());
+        assert!(paused.check(Transition::Start).is_ok());
+        assert!(running.check(Transition::Start).is_err());
+
+        assert!(running.check(Transition::Pause).is_ok());
+        assert!(paused.check(Transition::Pause).is_err());
+        assert!(TimerState::Idle.check(Transition::Pause).is_err());
+
+        assert!(paused.check(Transition::Resume).is_ok());
+        assert!(running.check(Transition::Resume).is_err());
+
+        assert!(running.check(Transition::Stop).is_ok());
+        assert!(paused.check(Transition::Stop).is_ok());
+        assert!(TimerState::Idle.check(Transition::Stop).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+use crate::backend::TrackerBackend;
+use crate::error::{Error, Result};
+use crate::models::IssueRef;
+use crate::tracker::{format_gitlab_time, Tracker};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::hash_map::{Entry, HashMap};
+use std::sync::Mutex;
+
+/// Espera base e máxima (segundos) entre tentativas de um mesmo lançamento
+const BACKOFF_BASE: i64 = 30;
+const BACKOFF_MAX: i64 = 3600;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct OutboxStatus {
+    pub pending: i64,
+    pub last_error: Option<String>,
+}
+
+struct OutboxEntry {
+    id: i64,
+    session_id: i64,
+    project_id: i64,
+    issue_id: i64,
+    issue_iid: Option<i64>,
+    seconds: i64,
+    duration: String,
+    summary: String,
+    attempts: i64,
+}
+
+/// Coloca na fila de envio o tempo da sessão finalizada que ainda não chegou ao rastreador.
+///
+/// Cada sessão tem no máximo um lançamento na fila, com a diferença entre o tempo atual
+/// e o já enviado (`synced_seconds`). Sessão editada depois de sincronizada fica como
+/// `alterado` até o ajuste (negativo, se o tempo diminuiu) ser enviado.
+pub fn enqueue_session(conn: &Connection, session_id: i64) -> Result<()> {
+    let session = conn.query_row(
+        "SELECT label, status, synced_seconds FROM sessions WHERE id=?1",
+        params![session_id],
+        |row| Ok((
+            row.get::<_, String>(0).unwrap_or_default(),
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, i64>(2)?,
+        )),
+    ).optional()?;
+    // sessão apagada ou juntada a outra enquanto o lançamento estava em voo
+    let Some((label, status, synced_seconds)) = session else {
+        return Ok(());
+    };
+    if status.as_deref() != Some("finalizado") {
+        return Ok(());
+    }
+
+    let seconds = Tracker::new(conn).decided_seconds(session_id)? - synced_seconds;
+    if seconds == 0 {
+        conn.execute("DELETE FROM sync_outbox WHERE session_id=?1", params![session_id])?;
+        if synced_seconds != 0 {
+            conn.execute(
+                "UPDATE sessions SET sync_status='sincronizado' WHERE id=?1",
+                params![session_id],
+            )?;
+        }
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO sync_outbox (session_id, seconds, duration, summary)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(session_id) DO UPDATE
+         SET seconds=excluded.seconds, duration=excluded.duration, summary=excluded.summary",
+        params![session_id, seconds, format_gitlab_time(seconds), label],
+    )?;
+    conn.execute(
+        "UPDATE sessions SET sync_status=?2 WHERE id=?1",
+        params![session_id, if synced_seconds == 0 { "pendente" } else { "alterado" }],
+    )?;
+    Ok(())
+}
+
+/// Envia pelo `api` os lançamentos do perfil cuja próxima tentativa já venceu.
+///
+/// O lock do banco só é segurado entre as chamadas à API. Retorna quantos
+/// lançamentos foram tentados (com sucesso ou não).
+pub async fn send_due<A: TrackerBackend>(conn: &Mutex<Connection>, profile_id: i64, api: &A) -> Result<usize> {
+    let entries = {
+        let conn = conn.lock()?;
+        let entries = due_entries(&conn, profile_id)?;
+        for entry in &entries {
+            conn.execute(
+                "UPDATE sync_outbox SET sending_at=strftime('%s','now') WHERE id=?1",
+                params![entry.id],
+            )?;
+        }
+        entries
+    };
+    let attempted = entries.len();
+
+    for entry in entries {
+        let result = match issue_iid(conn, api, &entry).await {
+            Ok(iid) => api.log_time(entry.project_id, iid, entry.seconds, &entry.summary).await,
+            Err(e) => Err(e),
+        };
+
+        let conn = conn.lock()?;
+        match result {
+            Ok(time_stats) => {
+                let response = serde_json::to_string(&time_stats).unwrap_or_default();
+                mark_sent(&conn, &entry, &response)?
+            }
+            Err(e) => {
+                eprintln!("Erro ao enviar sessão {} para {}: {}", entry.session_id, api.base_url(), e);
+                if let Error::Partial { sent, .. } = e {
+                    mark_progress(&conn, &entry, sent)?;
+                }
+                mark_failed(&conn, &entry, &e.to_string())?
+            }
+        }
+    }
+    Ok(attempted)
+}
+
+/// `iid` da issue da sessão, resolvido pela API quando a sessão é antiga
+async fn issue_iid<A: TrackerBackend>(conn: &Mutex<Connection>, api: &A, entry: &OutboxEntry) -> Result<i64> {
+    if let Some(iid) = entry.issue_iid {
+        return Ok(iid);
+    }
+    let issue = IssueRef::from(&api.find_issue(entry.project_id, entry.issue_id).await?);
+    let conn = conn.lock()?;
+    save_issue_ref(&conn, entry.session_id, &issue)?;
+    Ok(issue.iid)
+}
+
+fn save_issue_ref(conn: &Connection, session_id: i64, issue: &IssueRef) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE sessions SET issue_iid=?2, issue_title=?3, issue_web_url=?4 WHERE id=?1",
+        params![session_id, issue.iid, issue.title, issue.web_url],
+    )
+}
+
+/// Preenche `issue_iid`, título e link das sessões do perfil gravadas antes dessas colunas existirem
+pub async fn backfill_issue_refs<A: TrackerBackend>(conn: &Mutex<Connection>, profile_id: i64, api: &A) -> Result<()> {
+    let sessions = {
+        let conn = conn.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, issue_id FROM sessions
+             WHERE profile_id=?1 AND issue_iid IS NULL AND project_id IS NOT NULL AND issue_id IS NOT NULL",
+        )?;
+        let sessions = stmt
+            .query_map(params![profile_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        sessions
+    };
+
+    let mut resolved: HashMap<(i64, i64), Option<IssueRef>> = HashMap::new();
+    for (session_id, project_id, issue_id) in sessions {
+        if let Entry::Vacant(slot) = resolved.entry((project_id, issue_id)) {
+            slot.insert(match api.find_issue(project_id, issue_id).await {
+                Ok(issue) => Some(IssueRef::from(&issue)),
+                Err(e) => {
+                    eprintln!("Erro ao resolver issue {} do projeto {}: {}", issue_id, project_id, e);
+                    None
+                }
+            });
+        }
+        if let Some(Some(issue)) = resolved.get(&(project_id, issue_id)) {
+            let conn = conn.lock()?;
+            save_issue_ref(&conn, session_id, issue)?;
+        }
+    }
+    Ok(())
+}
+
+fn due_entries(conn: &Connection, profile_id: i64) -> rusqlite::Result<Vec<OutboxEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT o.id, o.session_id, s.project_id, s.issue_id, s.issue_iid,
+                COALESCE(o.seconds, (SELECT SUM(end_time - start_time) FROM session_intervals i
+                                     WHERE i.session_id = o.session_id AND i.end_time IS NOT NULL), 0),
+                o.duration, o.summary, o.attempts
+         FROM sync_outbox o
+         JOIN sessions s ON s.id = o.session_id
+         WHERE s.profile_id = ?1 AND o.next_attempt_at <= strftime('%s','now')
+         ORDER BY o.id",
+    )?;
+    let rows = stmt.query_map(params![profile_id], |row| {
+        Ok(OutboxEntry {
+            id: row.get(0)?,
+            session_id: row.get(1)?,
+            project_id: row.get(2)?,
+            issue_id: row.get(3)?,
+            issue_iid: row.get(4)?,
+            seconds: row.get(5)?,
+            duration: row.get(6)?,
+            summary: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+            attempts: row.get(8)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Registra o envio e enfileira o que a sessão tiver ganho enquanto o lançamento estava em voo
+fn mark_sent(conn: &Connection, entry: &OutboxEntry, response: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE sessions
+         SET sync_status='sincronizado', synced_duration=?2, synced_seconds=synced_seconds + ?4,
+             synced_at=strftime('%s','now'), sync_response=?3
+         WHERE id=?1",
+        params![entry.session_id, entry.duration, response, entry.seconds],
+    )?;
+    conn.execute("DELETE FROM sync_outbox WHERE id=?1", params![entry.id])?;
+    enqueue_session(conn, entry.session_id)
+}
+
+/// Guarda a parte do lançamento que chegou antes da falha; a próxima tentativa envia só o resto
+fn mark_progress(conn: &Connection, entry: &OutboxEntry, sent: i64) -> rusqlite::Result<()> {
+    let left = entry.seconds - sent;
+    conn.execute(
+        "UPDATE sessions SET synced_seconds=synced_seconds + ?2 WHERE id=?1",
+        params![entry.session_id, sent],
+    )?;
+    conn.execute(
+        "UPDATE sync_outbox SET seconds=?2, duration=?3 WHERE id=?1",
+        params![entry.id, left, format_gitlab_time(left)],
+    )?;
+    Ok(())
+}
+
+fn mark_failed(conn: &Connection, entry: &OutboxEntry, error: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE sync_outbox
+         SET attempts=attempts + 1, last_error=?2, sending_at=NULL,
+             next_attempt_at=strftime('%s','now') + ?3
+         WHERE id=?1",
+        params![entry.id, error, backoff_secs(entry.attempts)],
+    )?;
+    Ok(())
+}
+
+/// Espera exponencial: 30s, 60s, 120s... limitada a 1h
+fn backoff_secs(attempts: i64) -> i64 {
+    BACKOFF_BASE
+        .saturating_mul(1_i64 << attempts.clamp(0, 16))
+        .min(BACKOFF_MAX)
+}
+
+/// Quantidade de lançamentos aguardando envio e o último erro
+pub fn outbox_status(conn: &Connection) -> Result<OutboxStatus> {
+    let pending = conn.query_row("SELECT COUNT(*) FROM sync_outbox", [], |row| row.get(0))?;
+    let last_error = conn.query_row(
+        "SELECT last_error FROM sync_outbox
+         WHERE last_error IS NOT NULL ORDER BY next_attempt_at DESC LIMIT 1",
+        [],
+        |row| row.get(0),
+    ).ok();
+    Ok(OutboxStatus { pending, last_error })
+}
+
+/// Se o lançamento da sessão está a caminho do rastreador agora
+pub fn is_sending(conn: &Connection, session_id: i64) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sync_outbox WHERE session_id=?1 AND sending_at IS NOT NULL",
+        params![session_id],
+        |row| row.get(0),
+    )?)
+}
+
+/// Solta os lançamentos marcados como em envio por uma execução que não terminou
+pub fn release_sending(conn: &Connection) -> Result<()> {
+    conn.execute("UPDATE sync_outbox SET sending_at=NULL WHERE sending_at IS NOT NULL", [])?;
+    Ok(())
+}
+
+/// Antecipa a próxima tentativa de todos os lançamentos pendentes
+pub fn retry_all(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "UPDATE sync_outbox SET next_attempt_at=strftime('%s','now')",
+        [],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_an_hour() {
+        assert_eq!(backoff_secs(0), 30);
+        assert_eq!(backoff_secs(1), 60);
+        assert_eq!(backoff_secs(3), 240);
+        assert_eq!(backoff_secs(7), 3600);
+        assert_eq!(backoff_secs(100), 3600);
+    }
+}
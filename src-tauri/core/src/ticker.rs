@@ -0,0 +1,121 @@
+use crate::error::Result;
+use crate::state::TimerState;
+use crate::tracker::{format_gitlab_time, Clock, Tracker};
+use rusqlite::params;
+use serde::Serialize;
+
+/// Sessão atual lida do banco só nas transições; o tempo corrido é calculado a partir dela
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TimerSnapshot {
+    pub session_id: Option<i64>,
+    /// `runner`, `pause` ou `idle`
+    pub status: String,
+    pub profile_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub project_id: Option<i64>,
+    pub issue_id: Option<i64>,
+    pub issue_iid: Option<i64>,
+    pub issue_title: Option<String>,
+    pub label: Option<String>,
+    /// Soma dos intervalos já fechados
+    pub closed_seconds: i64,
+    /// Início do intervalo aberto, enquanto o timer roda
+    pub running_since: Option<i64>,
+}
+
+/// O que vai para as janelas a cada segundo (`timer://tick`)
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Tick {
+    #[serde(flatten)]
+    pub session: TimerSnapshot,
+    pub elapsed_seconds: i64,
+    /// Tempo no formato do GitLab (`1h30m`)
+    pub elapsed: String,
+}
+
+impl TimerSnapshot {
+    /// Nenhuma sessão rodando ou pausada
+    pub fn idle() -> Self {
+        TimerSnapshot {
+            session_id: None,
+            status: "idle".into(),
+            profile_id: None,
+            group_id: None,
+            project_id: None,
+            issue_id: None,
+            issue_iid: None,
+            issue_title: None,
+            label: None,
+            closed_seconds: 0,
+            running_since: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    /// Tempo da sessão em `now`, sem consultar o banco
+    pub fn elapsed(&self, now: i64) -> i64 {
+        self.closed_seconds + self.running_since.map_or(0, |since| (now - since).max(0))
+    }
+
+    pub fn tick(&self, now: i64) -> Tick {
+        let elapsed_seconds = self.elapsed(now);
+        Tick { session: self.clone(), elapsed_seconds, elapsed: format_gitlab_time(elapsed_seconds) }
+    }
+}
+
+impl<C: Clock> Tracker<'_, C> {
+    /// Sessão rodando ou, se nenhuma, a pausada mais recente
+    pub fn snapshot(&self) -> Result<TimerSnapshot> {
+        let state = self.state()?;
+        let Some(session_id) = state.session_id() else {
+            return Ok(TimerSnapshot::idle());
+        };
+        let mut session = self.conn.query_row(
+            "SELECT profile_id, group_id, project_id, issue_id, issue_iid, issue_title, label
+             FROM sessions WHERE id=?1",
+            params![session_id],
+            |row| {
+                Ok(TimerSnapshot {
+                    session_id: Some(session_id),
+                    status: state.status().into(),
+                    profile_id: row.get(0)?,
+                    group_id: row.get(1)?,
+                    project_id: row.get(2)?,
+                    issue_id: row.get(3)?,
+                    issue_iid: row.get(4)?,
+                    issue_title: row.get(5)?,
+                    label: row.get(6)?,
+                    closed_seconds: 0,
+                    running_since: None,
+                })
+            },
+        )?;
+
+        let (closed_seconds, open_since): (i64, Option<i64>) = self.conn.query_row(
+            "SELECT COALESCE(SUM(end_time - start_time), 0), MAX(CASE WHEN end_time IS NULL THEN start_time END)
+             FROM session_intervals WHERE session_id=?1",
+            params![session.session_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        session.closed_seconds = closed_seconds;
+        session.running_since = open_since.filter(|_| matches!(state, TimerState::Running { .. }));
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elapsed_counts_the_open_interval() {
+        let paused = TimerSnapshot { closed_seconds: 600, ..TimerSnapshot::idle() };
+        assert_eq!(paused.elapsed(5_000), 600);
+
+        let running = TimerSnapshot { running_since: Some(4_910), ..paused };
+        assert_eq!(running.tick(5_000).elapsed, "11m30s");
+    }
+}
@@ -0,0 +1,239 @@
+use crate::error::Result;
+use crate::models::IssueRef;
+use crate::state::{SwitchAction, TimerState, Transition};
+use crate::sync;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Hora atual em segundos Unix; nos testes o relógio é controlado
+pub trait Clock {
+    fn now(&self) -> i64;
+}
+
+/// Relógio do sistema
+#[derive(Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0)
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> i64 {
+        (**self).now()
+    }
+}
+
+/// (id, perfil, grupo, projeto, issue, label, status, tempo formatado)
+pub type LastSession = (i64, i64, i64, i64, i64, String, String, String);
+
+/// Duração no formato do GitLab; negativa (`-30m`) desconta tempo da issue
+pub fn format_gitlab_time(total_seconds: i64) -> String {
+    if total_seconds < 0 {
+        return format!("-{}", format_gitlab_time(-total_seconds));
+    }
+    let mut secs = total_seconds;
+    let days = secs / 86400;
+    secs %= 86400;
+    let hours = secs / 3600;
+    secs %= 3600;
+    let minutes = secs / 60;
+    secs %= 60;
+
+    let mut result = String::new();
+    if days > 0 { result.push_str(&format!("{}d", days)); }
+    if hours > 0 { result.push_str(&format!("{}h", hours)); }
+    if minutes > 0 { result.push_str(&format!("{}m", minutes)); }
+    if secs > 0 || result.is_empty() { result.push_str(&format!("{}s", secs)); }
+    result
+}
+
+/// Sessões de tempo gravadas no SQLite: iniciar, pausar, retomar, finalizar e somar.
+///
+/// Uma sessão (`sessions`) é formada por intervalos (`session_intervals`); o intervalo
+/// sem `end_time` é o que está correndo.
+pub struct Tracker<'a, C = SystemClock> {
+    pub(crate) conn: &'a Connection,
+    clock: C,
+}
+
+impl<'a> Tracker<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Tracker { conn, clock: SystemClock }
+    }
+}
+
+impl<'a, C: Clock> Tracker<'a, C> {
+    pub fn with_clock(conn: &'a Connection, clock: C) -> Self {
+        Tracker { conn, clock }
+    }
+
+    pub(crate) fn now(&self) -> i64 {
+        self.clock.now()
+    }
+
+    /// Sessão com o timer rodando, se houver
+    pub fn running_session(&self) -> Result<Option<i64>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id FROM sessions WHERE status='runner' ORDER BY id DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Cria a sessão no perfil e abre o primeiro intervalo
+    pub fn start(&self, profile_id: i64, group_id: i64, project_id: i64, issue: &IssueRef, label: &str) -> Result<i64> {
+        self.state()?.check(Transition::Start)?;
+        let now = self.now();
+
+        self.conn.execute(
+            "INSERT INTO sessions (profile_id, group_id, project_id, issue_id, issue_iid, issue_title, issue_web_url, label, status, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'runner', ?9)",
+            params![profile_id, group_id, project_id, issue.id, issue.iid, issue.title, issue.web_url, label, now],
+        )?;
+        let session_id = self.conn.last_insert_rowid();
+        self.open_interval(session_id, now)?;
+        Ok(session_id)
+    }
+
+    /// Troca de tarefa numa transação só: finaliza ou pausa a sessão atual e começa outra.
+    ///
+    /// Com `SwitchAction::Stop` a sessão pausada atual também é finalizada.
+    pub fn switch_task(
+        &self,
+        profile_id: i64,
+        group_id: i64,
+        project_id: i64,
+        issue: &IssueRef,
+        label: &str,
+        action: SwitchAction,
+    ) -> Result<i64> {
+        let tx = self.conn.unchecked_transaction()?;
+        match (self.state()?, action) {
+            (TimerState::Running { session_id, .. }, SwitchAction::Pause) => self.pause(session_id)?,
+            (TimerState::Running { session_id, .. } | TimerState::Paused { session_id }, SwitchAction::Stop) => {
+                self.stop(session_id)?
+            }
+            _ => {}
+        }
+        let session_id = self.start(profile_id, group_id, project_id, issue, label)?;
+        tx.commit()?;
+        Ok(session_id)
+    }
+
+    /// Pausa a sessão que está rodando
+    pub fn pause(&self, session_id: i64) -> Result<()> {
+        self.session_state(session_id)?.check(Transition::Pause)?;
+        let now = self.now();
+        self.close_interval(session_id, now)?;
+        self.set_status(session_id, "pause", now)
+    }
+
+    /// Retoma a sessão pausada, se nenhuma outra estiver rodando
+    pub fn resume(&self, session_id: i64) -> Result<()> {
+        self.session_state(session_id)?.check(Transition::Resume)?;
+        self.state()?.check(Transition::Start)?;
+        let now = self.now();
+        self.open_interval(session_id, now)?;
+        self.set_status(session_id, "runner", now)
+    }
+
+    /// Finaliza a sessão e coloca o tempo na fila de envio
+    pub fn stop(&self, session_id: i64) -> Result<()> {
+        self.session_state(session_id)?.check(Transition::Stop)?;
+        let now = self.now();
+        self.close_interval(session_id, now)?;
+        self.set_status(session_id, "finalizado", now)?;
+        sync::enqueue_session(self.conn, session_id)
+    }
+
+    /// Soma em segundos os intervalos da sessão (intervalo aberto conta até agora)
+    pub fn session_seconds(&self, session_id: i64) -> Result<i64> {
+        let total: Option<i64> = self.conn.query_row(
+            "SELECT SUM(COALESCE(end_time, ?2) - start_time)
+             FROM session_intervals
+             WHERE session_id=?1",
+            params![session_id, self.now()],
+            |row| row.get(0),
+        )?;
+        Ok(total.unwrap_or(0))
+    }
+
+    /// Tempo acumulado no formato do GitLab (`1h30m`)
+    pub fn session_time(&self, session_id: i64) -> Result<String> {
+        Ok(format_gitlab_time(self.session_seconds(session_id)?))
+    }
+
+    /// Sessão atual (rodando ou pausada), para reabrir a janela; só lê, sem mudar o estado
+    pub fn last_session(&self) -> Result<Option<LastSession>> {
+        let state = self.state()?;
+        let Some(session_id) = state.session_id() else {
+            return Ok(None);
+        };
+        let (profile_id, group_id, project_id, issue_id, label) = self.conn.query_row(
+            "SELECT COALESCE(profile_id, 0), group_id, project_id, issue_id, label FROM sessions WHERE id=?1",
+            params![session_id],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            },
+        )?;
+        let preview = self.session_time(session_id)?;
+
+        Ok(Some((session_id, profile_id, group_id, project_id, issue_id, label, state.status().to_string(), preview)))
+    }
+
+    fn open_interval(&self, session_id: i64, now: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO session_intervals (session_id, start_time, heartbeat_at)
+             VALUES (?1, ?2, ?2)",
+            params![session_id, now],
+        )?;
+        Ok(())
+    }
+
+    fn close_interval(&self, session_id: i64, now: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE session_intervals
+             SET end_time = ?2
+             WHERE session_id=?1 AND end_time IS NULL",
+            params![session_id, now],
+        )?;
+        Ok(())
+    }
+
+    fn set_status(&self, session_id: i64, status: &str, now: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET status=?2, updated_at=?3, auto_paused=0 WHERE id=?1",
+            params![session_id, status, now],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_like_gitlab() {
+        assert_eq!(format_gitlab_time(0), "0s");
+        assert_eq!(format_gitlab_time(59), "59s");
+        assert_eq!(format_gitlab_time(5400), "1h30m");
+        assert_eq!(format_gitlab_time(90061), "1d1h1m1s");
+        assert_eq!(format_gitlab_time(-1800), "-30m");
+    }
+}
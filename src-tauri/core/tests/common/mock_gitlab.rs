@@ -0,0 +1,276 @@
+//! GitLab de mentira para os testes: um servidor HTTP local que responde como a
+//! API v4 (com paginação, ETag e erros injetados), para nunca tocar no GitLab real.
+
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracetimedev_core::gitlab::GitLabClient;
+use tracetimedev_core::http::{self, HttpSettings};
+use wiremock::matchers::{header, method, path, path_regex};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Token aceito pelo servidor; qualquer outro recebe 401
+pub const TOKEN: &str = "glpat-test-token";
+
+/// Id do usuário dono de `TOKEN`
+pub const USER_ID: i64 = 42;
+
+pub struct MockGitLab {
+    pub server: MockServer,
+}
+
+impl MockGitLab {
+    /// Sobe o servidor já respondendo `/user` e recusando tokens errados
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+
+        Mock::given(|req: &Request| !has_token(req))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({ "message": "401 Unauthorized" })))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v4/user"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": USER_ID,
+                "username": "dev",
+                "name": "Dev Tester",
+                "web_url": "https://gitlab.example.com/dev"
+            })))
+            .mount(&server)
+            .await;
+
+        MockGitLab { server }
+    }
+
+    pub fn url(&self) -> String {
+        self.server.uri()
+    }
+
+    pub fn client(&self) -> GitLabClient {
+        self.client_with_timeout(Duration::from_secs(5))
+    }
+
+    /// Cliente com timeout curto, para testar respostas lentas
+    pub fn client_with_timeout(&self, timeout: Duration) -> GitLabClient {
+        let http = http::builder(&HttpSettings::default()).unwrap().timeout(timeout).build().unwrap();
+        GitLabClient::new(&http, &self.url(), TOKEN)
+    }
+
+    /// `/groups` com `total` grupos, paginados como o GitLab
+    pub async fn groups(&self, total: i64) {
+        let items = (1..=total).map(group).collect();
+        self.paginated("/api/v4/groups", items).await;
+    }
+
+    /// `/groups/:id/projects`
+    pub async fn projects(&self, group_id: i64, total: i64) {
+        let items = (1..=total).map(|id| project(group_id * 100 + id)).collect();
+        self.paginated(&format!("/api/v4/groups/{}/projects", group_id), items).await;
+    }
+
+    /// `/projects/:id/issues` com as issues dadas
+    pub async fn issues(&self, project_id: i64, issues: Vec<Value>) {
+        self.paginated(&format!("/api/v4/projects/{}/issues", project_id), issues).await;
+    }
+
+    /// `/issues/:id` (só administradores) com a issue dada
+    pub async fn issue(&self, issue: Value) {
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v4/issues/{}", issue["id"])))
+            .respond_with(ResponseTemplate::new(200).set_body_json(issue))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// `/projects/:id/issues/:iid/notes`
+    pub async fn notes(&self, project_id: i64, issue_iid: i64, total: i64) {
+        let items = (1..=total).map(note).collect();
+        self.paginated(&format!("/api/v4/projects/{}/issues/{}/notes", project_id, issue_iid), items)
+            .await;
+    }
+
+    /// `POST /projects/:id/issues/:iid/add_spent_time`
+    pub async fn add_spent_time(&self, project_id: i64, issue_iid: i64) {
+        Mock::given(method("POST"))
+            .and(path(format!("/api/v4/projects/{}/issues/{}/add_spent_time", project_id, issue_iid)))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "time_estimate": 0,
+                "total_time_spent": 900,
+                "human_time_estimate": null,
+                "human_total_time_spent": "15m"
+            })))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// `/personal_access_tokens/self` com os escopos e a validade dados
+    pub async fn token(&self, scopes: &[&str], expires_at: Option<&str>) {
+        Mock::given(method("GET"))
+            .and(path("/api/v4/personal_access_tokens/self"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": 1,
+                "name": "tracetimedev",
+                "revoked": false,
+                "active": true,
+                "scopes": scopes,
+                "user_id": USER_ID,
+                "expires_at": expires_at
+            })))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Responde `status` a qualquer método em `path`, antes das respostas normais
+    pub async fn fail(&self, path_pattern: &str, status: u16) {
+        Mock::given(path_regex(path_pattern))
+            .respond_with(
+                ResponseTemplate::new(status)
+                    .insert_header("Retry-After", "60")
+                    .set_body_json(json!({ "message": format!("{} erro injetado", status) })),
+            )
+            .with_priority(2)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Demora `delay` para responder em `path`, para estourar o timeout do cliente
+    pub async fn slow(&self, path_pattern: &str, delay: Duration) {
+        Mock::given(path_regex(path_pattern))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])).set_delay(delay))
+            .with_priority(2)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Responde 304 quando o cliente manda `If-None-Match: etag`
+    pub async fn not_modified(&self, path_pattern: &str, etag: &str) {
+        Mock::given(path_regex(path_pattern))
+            .and(header("If-None-Match", etag))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(2)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Requisições recebidas em `path` (sem query string)
+    pub async fn requests_to(&self, path: &str) -> Vec<Request> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|req| req.url.path() == path)
+            .collect()
+    }
+
+    async fn paginated(&self, route: &str, items: Vec<Value>) {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(Paginated { base: Url::parse(&self.url()).unwrap(), items })
+            .mount(&self.server)
+            .await;
+    }
+}
+
+fn has_token(req: &Request) -> bool {
+    req.headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        == Some(&format!("Bearer {}", TOKEN))
+}
+
+/// Resposta paginada no formato do GitLab: `page`/`per_page` na query,
+/// `X-Page`, `X-Next-Page`, `X-Total`, `Link` e `ETag` nos cabeçalhos
+struct Paginated {
+    base: Url,
+    items: Vec<Value>,
+}
+
+impl Paginated {
+    /// URL absoluta da página seguinte, como o GitLab manda no `Link`
+    fn next_url(&self, current: &Url, page: usize) -> Url {
+        let pairs: Vec<(String, String)> = current
+            .query_pairs()
+            .filter(|(key, _)| key != "page")
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        let mut next = self.base.join(current.path()).unwrap();
+        next.query_pairs_mut().extend_pairs(pairs).append_pair("page", &page.to_string());
+        next
+    }
+}
+
+impl Respond for Paginated {
+    fn respond(&self, req: &Request) -> ResponseTemplate {
+        let param = |key: &str| {
+            req.url
+                .query_pairs()
+                .find(|(k, _)| k == key)
+                .and_then(|(_, v)| v.parse::<usize>().ok())
+        };
+        let page = param("page").unwrap_or(1).max(1);
+        let per_page = param("per_page").unwrap_or(20).clamp(1, 100);
+
+        let start = ((page - 1) * per_page).min(self.items.len());
+        let end = (start + per_page).min(self.items.len());
+        let has_next = end < self.items.len();
+
+        let mut response = ResponseTemplate::new(200)
+            .insert_header("X-Page", page.to_string())
+            .insert_header("X-Per-Page", per_page.to_string())
+            .insert_header("X-Total", self.items.len().to_string())
+            .insert_header("X-Next-Page", if has_next { (page + 1).to_string() } else { String::new() })
+            .insert_header("ETag", format!("W/\"{}-{}\"", req.url.path(), self.items.len()))
+            .set_body_json(Value::Array(self.items[start..end].to_vec()));
+
+        if has_next {
+            response = response.insert_header("Link", format!("<{}>; rel=\"next\"", self.next_url(&req.url, page + 1)));
+        }
+        response
+    }
+}
+
+pub fn group(id: i64) -> Value {
+    json!({
+        "id": id,
+        "name": format!("Grupo {}", id),
+        "full_path": format!("grupo-{}", id),
+        "full_name": format!("Grupo {}", id),
+        "parent_id": null
+    })
+}
+
+pub fn project(id: i64) -> Value {
+    json!({
+        "id": id,
+        "name": format!("Projeto {}", id),
+        "name_with_namespace": format!("Grupo / Projeto {}", id),
+        "path_with_namespace": format!("grupo/projeto-{}", id)
+    })
+}
+
+pub fn issue(project_id: i64, iid: i64) -> Value {
+    json!({
+        "id": project_id * 1000 + iid,
+        "iid": iid,
+        "project_id": project_id,
+        "title": format!("Issue {}", iid),
+        "state": "opened",
+        "web_url": format!("https://gitlab.example.com/grupo/projeto/-/issues/{}", iid),
+        "labels": ["backend"],
+        "milestone": null,
+        "assignees": [{ "id": USER_ID, "username": "dev", "name": "Dev Tester" }],
+        "time_stats": { "time_estimate": 3600, "total_time_spent": 0 }
+    })
+}
+
+fn note(id: i64) -> Value {
+    json!({
+        "id": id,
+        "body": format!("Comentário {}", id),
+        "author": { "id": USER_ID, "username": "dev", "name": "Dev Tester" },
+        "created_at": "2026-01-05T12:00:00.000Z",
+        "system": false
+    })
+}
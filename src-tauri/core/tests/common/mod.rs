@@ -0,0 +1,62 @@
+#![allow(dead_code)]
+
+pub mod mock_gitlab;
+
+use rusqlite::Connection;
+use std::cell::Cell;
+use tracetimedev_core::http::{self, HttpSettings};
+use tracetimedev_core::models::IssueRef;
+use tracetimedev_core::{migrations, Clock, Tracker};
+
+/// Perfil das sessões criadas nos testes
+pub const PROFILE_ID: i64 = 1;
+
+/// Banco em memória com o schema atual
+pub fn db() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    migrations::migrate(&mut conn, None).unwrap();
+    conn
+}
+
+/// Cliente HTTP com a configuração padrão do app
+pub fn http() -> reqwest::Client {
+    http::client(&HttpSettings::default()).unwrap()
+}
+
+/// Relógio parado que só anda quando o teste manda
+pub struct FakeClock(Cell<i64>);
+
+impl FakeClock {
+    pub fn at(now: i64) -> Self {
+        FakeClock(Cell::new(now))
+    }
+
+    pub fn advance(&self, secs: i64) {
+        self.0.set(self.0.get() + secs);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> i64 {
+        self.0.get()
+    }
+}
+
+pub fn issue(iid: i64) -> IssueRef {
+    IssueRef {
+        id: 1000 + iid,
+        iid,
+        title: format!("Issue {}", iid),
+        web_url: format!("https://gitlab.example.com/g/p/-/issues/{}", iid),
+    }
+}
+
+/// Sessão de 15 minutos finalizada na issue 7 do projeto 2, já na fila de envio
+pub fn finished_session(conn: &Connection) -> i64 {
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(900);
+    tracker.stop(id).unwrap();
+    id
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use common::{db, finished_session, issue, FakeClock, PROFILE_ID};
+use rusqlite::Connection;
+use tracetimedev_core::editing::Span;
+use tracetimedev_core::sync::outbox_status;
+use tracetimedev_core::{Error, Tracker};
+
+fn span(start_time: i64, end_time: i64) -> Span {
+    Span { start_time, end_time }
+}
+
+fn queued_seconds(conn: &Connection, session_id: i64) -> Option<i64> {
+    conn.query_row("SELECT seconds FROM sync_outbox WHERE session_id=?1", [session_id], |row| row.get(0))
+        .ok()
+}
+
+fn sessions(conn: &Connection) -> i64 {
+    conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn manual_session_is_finished_and_queued() {
+    let conn = db();
+    let tracker = Tracker::with_clock(&conn, FakeClock::at(10_000));
+
+    let id = tracker
+        .create_manual(PROFILE_ID, 1, 2, &issue(7), "Development", &[span(1_000, 1_600), span(2_000, 2_300)])
+        .unwrap();
+
+    assert_eq!(tracker.session_seconds(id).unwrap(), 900);
+    let (status, manual): (String, bool) = conn
+        .query_row("SELECT status, manual FROM sessions WHERE id=?1", [id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap();
+    assert_eq!((status.as_str(), manual), ("finalizado", true));
+    assert_eq!(queued_seconds(&conn, id), Some(900));
+}
+
+#[test]
+fn overlapping_intervals_are_rejected() {
+    let conn = db();
+    finished_session(&conn); // 1000..1900
+    let tracker = Tracker::with_clock(&conn, FakeClock::at(10_000));
+
+    let err = tracker
+        .create_manual(PROFILE_ID, 1, 2, &issue(8), "Review", &[span(3_000, 3_100), span(1_800, 2_000)])
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidState(ref msg) if msg.contains("sobrepõe")), "{:?}", err);
+    // nada da sessão recusada fica gravado
+    assert_eq!(sessions(&conn), 1);
+
+    let err = tracker
+        .create_manual(PROFILE_ID, 1, 2, &issue(8), "Review", &[span(3_000, 3_100), span(3_050, 3_200)])
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidState(_)));
+}
+
+#[test]
+fn inverted_or_future_intervals_are_rejected() {
+    let conn = db();
+    let tracker = Tracker::with_clock(&conn, FakeClock::at(10_000));
+
+    for bad in [span(2_000, 1_000), span(9_000, 11_000)] {
+        let err = tracker.create_manual(PROFILE_ID, 1, 2, &issue(7), "Development", &[bad]).unwrap_err();
+        assert!(matches!(err, Error::InvalidState(_)), "{:?}", err);
+    }
+}
+
+#[test]
+fn editing_an_interval_updates_the_queue() {
+    let conn = db();
+    let id = finished_session(&conn);
+    let tracker = Tracker::with_clock(&conn, FakeClock::at(10_000));
+    let interval = tracker.intervals(id).unwrap()[0].id;
+
+    tracker.update_interval(interval, &span(1_000, 2_200)).unwrap();
+
+    assert_eq!(queued_seconds(&conn, id), Some(1_200));
+    assert_eq!(outbox_status(&conn).unwrap().pending, 1);
+}
+
+#[test]
+fn running_interval_cannot_be_edited() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    let interval = tracker.intervals(id).unwrap()[0].id;
+    clock.advance(1_000);
+
+    assert!(matches!(tracker.update_interval(interval, &span(500, 900)), Err(Error::InvalidState(_))));
+    assert!(matches!(tracker.delete_interval(interval), Err(Error::InvalidState(_))));
+    // o intervalo aberto também bloqueia lançamentos manuais até agora
+    let err = tracker.create_manual(PROFILE_ID, 1, 2, &issue(8), "Review", &[span(1_500, 1_800)]).unwrap_err();
+    assert!(matches!(err, Error::InvalidState(ref msg) if msg.contains("sobrepõe")), "{:?}", err);
+}
+
+#[test]
+fn deleting_the_last_interval_removes_the_unsent_session() {
+    let conn = db();
+    let id = finished_session(&conn);
+    let tracker = Tracker::with_clock(&conn, FakeClock::at(10_000));
+    let interval = tracker.intervals(id).unwrap()[0].id;
+
+    tracker.delete_interval(interval).unwrap();
+
+    assert_eq!(sessions(&conn), 0);
+    assert_eq!(outbox_status(&conn).unwrap().pending, 0);
+}
+
+#[test]
+fn split_cuts_the_interval_at_the_timestamp() {
+    let conn = db();
+    let id = finished_session(&conn); // 1000..1900
+    let tracker = Tracker::with_clock(&conn, FakeClock::at(10_000));
+
+    let new_id = tracker.split_session(id, 1_300).unwrap();
+
+    assert_eq!(tracker.session_seconds(id).unwrap(), 300);
+    assert_eq!(tracker.session_seconds(new_id).unwrap(), 600);
+    assert_eq!(tracker.intervals(new_id).unwrap()[0].start_time, 1_300);
+    assert_eq!((queued_seconds(&conn, id), queued_seconds(&conn, new_id)), (Some(300), Some(600)));
+
+    let err = tracker.split_session(id, 5_000).unwrap_err();
+    assert!(matches!(err, Error::InvalidState(_)));
+}
+
+#[test]
+fn splitting_a_paused_session_finishes_only_the_new_one() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(900);
+    tracker.pause(id).unwrap();
+
+    let new_id = tracker.split_session(id, 1_300).unwrap();
+
+    let status = |id: i64| -> String {
+        conn.query_row("SELECT status FROM sessions WHERE id=?1", [id], |row| row.get(0)).unwrap()
+    };
+    assert_eq!((status(id).as_str(), status(new_id).as_str()), ("pause", "finalizado"));
+    assert_eq!((queued_seconds(&conn, id), queued_seconds(&conn, new_id)), (None, Some(600)));
+}
+
+#[test]
+fn merge_joins_sessions_of_the_same_issue() {
+    let conn = db();
+    let tracker = Tracker::with_clock(&conn, FakeClock::at(10_000));
+    let first = tracker.create_manual(PROFILE_ID, 1, 2, &issue(7), "Development", &[span(1_000, 1_600)]).unwrap();
+    let second = tracker.create_manual(PROFILE_ID, 1, 2, &issue(7), "Development", &[span(2_000, 2_300)]).unwrap();
+    let other = tracker.create_manual(PROFILE_ID, 1, 2, &issue(8), "Review", &[span(3_000, 3_100)]).unwrap();
+
+    tracker.merge_sessions(first, second).unwrap();
+
+    assert_eq!(tracker.session_seconds(first).unwrap(), 900);
+    assert_eq!(tracker.intervals(first).unwrap().len(), 2);
+    assert_eq!(queued_seconds(&conn, first), Some(900));
+    assert_eq!(queued_seconds(&conn, second), None);
+    assert_eq!(sessions(&conn), 2);
+
+    let err = tracker.merge_sessions(first, other).unwrap_err();
+    assert!(matches!(err, Error::InvalidState(ref msg) if msg.contains("mesma issue")), "{:?}", err);
+}
@@ -0,0 +1,213 @@
+//! Cliente do Gitea contra um servidor de teste com as rotas da API v1
+
+mod common;
+
+use common::{db, finished_session, PROFILE_ID};
+use serde_json::json;
+use std::sync::Mutex;
+use tracetimedev_core::backend::{Backend, BackendKind, TrackerBackend};
+use tracetimedev_core::models::IssueFilter;
+use tracetimedev_core::editing::Span;
+use tracetimedev_core::sync::{outbox_status, retry_all, send_due};
+use tracetimedev_core::{Error, Tracker};
+use wiremock::matchers::{body_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const TOKEN: &str = "gitea-test-token";
+
+/// Servidor com usuário, organização, repositório e a issue #7
+async fn gitea() -> MockServer {
+    let server = MockServer::start().await;
+    let get = |route: &str, body: serde_json::Value| {
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1{}", route)))
+            .and(header("Authorization", format!("token {}", TOKEN).as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+    };
+
+    get("/user", json!({ "id": 5, "login": "dev", "full_name": "", "html_url": null }))
+        .mount(&server)
+        .await;
+    get("/user/orgs", json!([{ "id": 3, "name": "acme", "full_name": "Acme Ltda" }]))
+        .mount(&server)
+        .await;
+    get("/repos/search", json!({ "ok": true, "data": [{ "id": 2, "name": "app", "full_name": "acme/app" }] }))
+        .mount(&server)
+        .await;
+    get("/repositories/2", json!({ "id": 2, "name": "app", "full_name": "acme/app" }))
+        .mount(&server)
+        .await;
+    get("/repos/acme/app/issues", json!([{
+        "id": 1007,
+        "number": 7,
+        "title": "Login quebrado",
+        "state": "open",
+        "html_url": "https://gitea.example.com/acme/app/issues/7",
+        "labels": [{ "name": "bug" }],
+        "milestone": { "id": 1, "title": "v1" },
+        "assignees": [{ "id": 5, "login": "dev" }]
+    }]))
+    .mount(&server)
+    .await;
+    get("/repos/acme/app/issues/7/times", json!([{ "id": 1, "time": 600 }, { "id": 2, "time": 900 }]))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn client(server: &MockServer) -> Backend {
+    Backend::new(BackendKind::Gitea, &common::http(), &server.uri(), TOKEN)
+}
+
+#[tokio::test]
+async fn maps_user_orgs_and_repos() {
+    let server = gitea().await;
+    let client = client(&server);
+
+    let user = client.current_user().await.unwrap();
+    assert_eq!((user.id, user.username.as_str(), user.name.as_str()), (5, "dev", "dev"));
+
+    let orgs = client.containers(usize::MAX, None).await.unwrap().into_items();
+    assert_eq!((orgs[0].id, orgs[0].full_name.as_str()), (3, "Acme Ltda"));
+
+    let repos = client.projects(3, usize::MAX, None).await.unwrap().into_items();
+    assert_eq!(repos[0].path_with_namespace, "acme/app");
+    let search = &server.received_requests().await.unwrap()[2];
+    assert_eq!(search.url.query(), Some("limit=50&uid=3&exclusive=true"));
+}
+
+#[tokio::test]
+async fn issues_use_gitea_filter_and_gitlab_shape() {
+    let server = gitea().await;
+
+    let issues = client(&server)
+        .issues(2, &IssueFilter::assigned_to_me(), 0, usize::MAX, None)
+        .await
+        .unwrap()
+        .into_items();
+
+    assert_eq!((issues[0].id, issues[0].iid, issues[0].state.as_str()), (1007, 7, "opened"));
+    assert_eq!(issues[0].labels, ["bug"]);
+    let requests = server.received_requests().await.unwrap();
+    let list = requests.iter().find(|req| req.url.path() == "/api/v1/repos/acme/app/issues").unwrap();
+    assert_eq!(list.url.query(), Some("limit=50&type=issues&state=open&assigned_by=dev"));
+}
+
+#[tokio::test]
+async fn outbox_posts_seconds_to_gitea() {
+    let server = gitea().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/repos/acme/app/issues/7/times"))
+        .and(body_json(json!({ "time": 900 })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "time": 900 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let conn = db();
+    finished_session(&conn);
+    let conn = Mutex::new(conn);
+
+    assert_eq!(send_due(&conn, PROFILE_ID, &client(&server)).await.unwrap(), 1);
+
+    assert_eq!(outbox_status(&conn.lock().unwrap()).unwrap().pending, 0);
+    let response: String = conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT sync_response FROM sessions", [], |row| row.get(0))
+        .unwrap();
+    assert!(response.contains("\"total_time_spent\":1500"), "{}", response);
+}
+
+#[tokio::test]
+async fn negative_time_deletes_and_relogs_the_rest() {
+    let server = gitea().await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/repos/acme/app/issues/7/times/2"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    // tirar 300 s do lançamento de 900 s: apaga e relança 600 s
+    Mock::given(method("POST"))
+        .and(path("/api/v1/repos/acme/app/issues/7/times"))
+        .and(body_json(json!({ "time": 600 })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "time": 600 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    client(&server).log_time(2, 7, -300, "").await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let mine = requests
+        .iter()
+        .find(|req| req.method.as_str() == "GET" && req.url.path().ends_with("/times"))
+        .unwrap();
+    assert_eq!(mine.url.query(), Some("limit=50&user=dev"));
+}
+
+#[tokio::test]
+async fn negative_time_beyond_own_time_is_refused() {
+    let server = gitea().await;
+
+    let result = client(&server).log_time(2, 7, -2000, "").await;
+
+    assert!(matches!(result, Err(Error::InvalidState(_))), "{:?}", result.err());
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests.iter().all(|req| req.method.as_str() == "GET"));
+}
+
+#[tokio::test]
+async fn failed_relog_is_retried_without_deleting_again() {
+    let server = gitea().await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/repos/acme/app/issues/7/times/2"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/repos/acme/app/issues/7/times"))
+        .and(body_json(json!({ "time": 200 })))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(2)
+        .mount(&server)
+        .await;
+    let conn = db();
+    let id = finished_session(&conn);
+    conn.execute("DELETE FROM sync_outbox", []).unwrap();
+    conn.execute("UPDATE sessions SET synced_seconds=900, sync_status='sincronizado'", []).unwrap();
+    // 900 s enviados viram 200: apaga o lançamento de 900 s e falha ao relançar 200 s
+    let tracker = Tracker::new(&conn);
+    let interval = tracker.intervals(id).unwrap()[0].id;
+    tracker.update_interval(interval, &Span { start_time: 1_000, end_time: 1_200 }).unwrap();
+    let conn = Mutex::new(conn);
+
+    send_due(&conn, PROFILE_ID, &client(&server)).await.unwrap();
+
+    let (synced, left): (i64, i64) = conn
+        .lock()
+        .unwrap()
+        .query_row(
+            "SELECT s.synced_seconds, o.seconds FROM sessions s JOIN sync_outbox o ON o.session_id = s.id",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!((synced, left), (0, 200));
+
+    retry_all(&conn.lock().unwrap()).unwrap();
+    send_due(&conn, PROFILE_ID, &client(&server)).await.unwrap();
+}
+
+#[tokio::test]
+async fn find_issue_searches_all_states() {
+    let server = gitea().await;
+
+    let issue = client(&server).find_issue(2, 1007).await.unwrap();
+
+    assert_eq!(issue.iid, 7);
+    let requests = server.received_requests().await.unwrap();
+    let list = requests.iter().find(|req| req.url.path().ends_with("/issues")).unwrap();
+    assert_eq!(list.url.query(), Some("limit=50&type=issues&state=all"));
+}
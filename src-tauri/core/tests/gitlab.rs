@@ -0,0 +1,253 @@
+mod common;
+
+use common::db;
+use common::mock_gitlab::{issue, MockGitLab, USER_ID};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracetimedev_core::backend::{Fetched, TrackerBackend};
+use tracetimedev_core::cache::{self, ListKind};
+use tracetimedev_core::config::{self, Profile};
+use tracetimedev_core::gitlab::{missing_scopes, GitLabClient};
+use tracetimedev_core::models::{Group, Issue, IssueFilter, Project};
+use tracetimedev_core::secrets::MemoryStore;
+use tracetimedev_core::Error;
+
+fn items<T>(fetched: Fetched<T>) -> Vec<T> {
+    match fetched {
+        Fetched::Items(items, _) => items,
+        Fetched::NotModified => panic!("esperava itens, veio 304"),
+    }
+}
+
+/// Banco com um perfil para o servidor de teste, com o limite de itens dado
+fn configured_db(gitlab: &MockGitLab, page_limit: i64) -> (Mutex<rusqlite::Connection>, i64) {
+    let conn = db();
+    let profile_id = config::save(&conn, &MemoryStore::default(), &Profile {
+        url: gitlab.url(),
+        token: common::mock_gitlab::TOKEN.into(),
+        gitlab_user_id: USER_ID,
+        page_limit: Some(page_limit),
+        ..Default::default()
+    })
+    .unwrap();
+    (Mutex::new(conn), profile_id)
+}
+
+#[tokio::test]
+async fn current_user_sends_the_token() {
+    let gitlab = MockGitLab::start().await;
+
+    let user = gitlab.client().current_user().await.unwrap();
+
+    assert_eq!((user.id, user.username.as_str()), (USER_ID, "dev"));
+}
+
+#[tokio::test]
+async fn wrong_token_is_an_auth_error() {
+    let gitlab = MockGitLab::start().await;
+    let client = GitLabClient::new(&common::http(), &gitlab.url(), "token-errado");
+
+    let err = client.current_user().await.unwrap_err();
+
+    assert!(matches!(err, Error::Auth(_)), "{:?}", err);
+}
+
+#[tokio::test]
+async fn read_only_token_lacks_time_tracking_scope() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.token(&["read_api", "read_user"], Some("2026-12-31")).await;
+
+    let token = gitlab.client().token_info().await.unwrap().unwrap();
+
+    assert_eq!(token.expires_at.as_deref(), Some("2026-12-31"));
+    assert_eq!(missing_scopes(&token), ["api"]);
+}
+
+#[tokio::test]
+async fn old_instance_without_token_endpoint_is_not_an_error() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.fail("^/api/v4/personal_access_tokens/self$", 404).await;
+
+    assert!(gitlab.client().token_info().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn lists_every_page_of_groups() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.groups(250).await;
+
+    let groups = items(gitlab.client().containers(usize::MAX, None).await.unwrap());
+
+    assert_eq!(groups.len(), 250);
+    assert_eq!(groups.last().unwrap().id, 250);
+    let pages: Vec<_> = gitlab
+        .requests_to("/api/v4/groups")
+        .await
+        .iter()
+        .map(|req| req.url.query().unwrap_or_default().to_string())
+        .collect();
+    assert_eq!(pages, ["per_page=100", "per_page=100&page=2", "per_page=100&page=3"]);
+}
+
+#[tokio::test]
+async fn page_limit_stops_paging_early() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.projects(5, 300).await;
+    let (conn, profile_id) = configured_db(&gitlab, 150);
+    let client = gitlab.client();
+    let key = cache::key(profile_id, ListKind::Projects, Some(5), None);
+
+    let projects: Vec<Project> = cache::fetch_list(&conn, profile_id, &key, ListKind::Projects, Some(5), false, |limit| {
+        client.projects(5, limit, None)
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(projects.len(), 150);
+    assert_eq!(gitlab.requests_to("/api/v4/groups/5/projects").await.len(), 2);
+}
+
+#[tokio::test]
+async fn issue_filter_becomes_query_params() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.issues(2, vec![issue(2, 1), issue(2, 2)]).await;
+    let client = gitlab.client();
+    let filter = IssueFilter { labels: Some("backend".into()), ..IssueFilter::assigned_to_me() };
+
+    let issues: Vec<Issue> = items(client.issues(2, &filter, USER_ID, usize::MAX, None).await.unwrap());
+
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].assignees[0].id, USER_ID);
+    let requests = gitlab.requests_to("/api/v4/projects/2/issues").await;
+    assert_eq!(
+        requests[0].url.query(),
+        Some("per_page=100&labels=backend&state=opened&assignee_id=42")
+    );
+}
+
+#[tokio::test]
+async fn cached_list_is_revalidated_with_etag() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.groups(3).await;
+    let (conn, profile_id) = configured_db(&gitlab, 1000);
+    let client = gitlab.client();
+    let key = cache::key(profile_id, ListKind::Groups, None, None);
+
+    let groups: Vec<Group> = cache::fetch_list(&conn, profile_id, &key, ListKind::Groups, None, true, |limit| {
+        client.containers(limit, None)
+    })
+    .await
+    .unwrap();
+    assert_eq!(groups.len(), 3);
+
+    let entry = cache::get::<Group>(&conn.lock().unwrap(), &key).unwrap().unwrap();
+    let etag = entry.etag.unwrap();
+    assert_eq!(entry.items.len(), 3);
+
+    gitlab.not_modified("^/api/v4/groups$", &etag).await;
+    let changed = cache::refresh_list(&conn, profile_id, &key, ListKind::Groups, None, |limit| {
+        client.containers(limit, Some(&etag))
+    })
+    .await
+    .unwrap();
+    assert!(!changed);
+
+    let changed = cache::refresh_list(&conn, profile_id, &key, ListKind::Groups, None, |limit| {
+        client.containers(limit, Some("W/\"antigo\""))
+    })
+    .await
+    .unwrap();
+    assert!(changed);
+}
+
+#[tokio::test]
+async fn lists_longer_than_a_page_are_not_revalidated() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.groups(150).await;
+
+    let etag = match gitlab.client().containers(usize::MAX, None).await.unwrap() {
+        Fetched::Items(items, etag) => {
+            assert_eq!(items.len(), 150);
+            etag
+        }
+        Fetched::NotModified => panic!("esperava itens, veio 304"),
+    };
+
+    assert_eq!(etag, None);
+}
+
+#[tokio::test]
+async fn finds_issue_by_global_id() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.issues(2, (1..=130).map(|iid| issue(2, iid)).collect()).await;
+
+    let found = gitlab.client().find_issue(2, 2_105).await.unwrap();
+    assert_eq!(found.iid, 105);
+    // a busca para na página da issue (a 2ª de 100 itens), sem pedir o resto
+    let pages = gitlab.server.received_requests().await.unwrap();
+    assert_eq!(pages.iter().filter(|req| req.url.path() == "/api/v4/projects/2/issues").count(), 2);
+
+    let err = gitlab.client().find_issue(2, 9_999).await.unwrap_err();
+    assert!(matches!(err, Error::NotFound(_)));
+}
+
+#[tokio::test]
+async fn finds_issue_directly_when_allowed() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.issue(issue(2, 125)).await;
+
+    let found = gitlab.client().find_issue(2, 2_125).await.unwrap();
+
+    assert_eq!(found.iid, 125);
+    let requests = gitlab.server.received_requests().await.unwrap();
+    assert!(requests.iter().all(|req| req.url.path() == "/api/v4/issues/2125"));
+}
+
+#[tokio::test]
+async fn lists_issue_notes() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.notes(2, 7, 120).await;
+
+    let notes = gitlab.client().issue_notes(2, 7).await.unwrap();
+
+    assert_eq!(notes.len(), 120);
+    assert_eq!(notes[0].author.username, "dev");
+}
+
+#[tokio::test]
+async fn rate_limit_keeps_the_status() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.fail("^/api/v4/groups$", 429).await;
+
+    let err = gitlab.client().containers(usize::MAX, None).await.err().unwrap();
+
+    assert!(matches!(err, Error::GitLab { status: 429, .. }), "{:?}", err);
+}
+
+#[tokio::test]
+async fn server_error_keeps_the_body() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.fail("^/api/v4/user$", 500).await;
+
+    match gitlab.client().current_user().await.unwrap_err() {
+        Error::GitLab { status, body } => {
+            assert_eq!(status, 500);
+            assert!(body.contains("erro injetado"));
+        }
+        err => panic!("erro inesperado: {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn slow_server_is_a_network_error() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.slow("^/api/v4/user$", Duration::from_secs(2)).await;
+
+    let err = gitlab
+        .client_with_timeout(Duration::from_millis(200))
+        .current_user()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Network(_)), "{:?}", err);
+}
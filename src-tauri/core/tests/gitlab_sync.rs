@@ -0,0 +1,118 @@
+//! Fila de envio falando com o servidor de teste, como faz o worker do app
+
+mod common;
+
+use common::mock_gitlab::{issue, MockGitLab};
+use common::{db, finished_session, PROFILE_ID};
+use rusqlite::Connection;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracetimedev_core::gitlab::GitLabClient;
+use tracetimedev_core::sync::{outbox_status, send_due};
+
+fn sync_status(conn: &Mutex<Connection>, session_id: i64) -> Option<String> {
+    conn.lock()
+        .unwrap()
+        .query_row("SELECT sync_status FROM sessions WHERE id=?1", [session_id], |row| row.get(0))
+        .unwrap()
+}
+
+/// Envia uma sessão finalizada e devolve o banco para conferência
+async fn send_one(client: &GitLabClient) -> (Mutex<Connection>, i64) {
+    let conn = db();
+    let id = finished_session(&conn);
+    let conn = Mutex::new(conn);
+    assert_eq!(send_due(&conn, PROFILE_ID, client).await.unwrap(), 1);
+    (conn, id)
+}
+
+fn last_error(conn: &Mutex<Connection>) -> String {
+    let status = outbox_status(&conn.lock().unwrap()).unwrap();
+    assert_eq!(status.pending, 1);
+    status.last_error.unwrap()
+}
+
+#[tokio::test]
+async fn posts_spent_time() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.add_spent_time(2, 7).await;
+
+    let (conn, id) = send_one(&gitlab.client()).await;
+
+    let requests = gitlab.requests_to("/api/v4/projects/2/issues/7/add_spent_time").await;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].url.query(), Some("duration=15m&summary=Development"));
+    assert_eq!(sync_status(&conn, id).as_deref(), Some("sincronizado"));
+    assert_eq!(outbox_status(&conn.lock().unwrap()).unwrap().pending, 0);
+}
+
+#[tokio::test]
+async fn resolves_iid_of_old_sessions_before_posting() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.issues(2, vec![issue(2, 3), issue(2, 9)]).await;
+    gitlab.add_spent_time(2, 9).await;
+    let conn = db();
+    conn.execute(
+        "INSERT INTO sessions (profile_id, group_id, project_id, issue_id, label, status) VALUES (1, 1, 2, 2009, 'Dev', 'finalizado')",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO sync_outbox (session_id, duration, summary) VALUES (last_insert_rowid(), '30m', 'Dev')",
+        [],
+    )
+    .unwrap();
+    let conn = Mutex::new(conn);
+
+    assert_eq!(send_due(&conn, PROFILE_ID, &gitlab.client()).await.unwrap(), 1);
+
+    assert_eq!(gitlab.requests_to("/api/v4/projects/2/issues/9/add_spent_time").await.len(), 1);
+    let iid: i64 = conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT issue_iid FROM sessions WHERE issue_id=2009", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(iid, 9);
+}
+
+#[tokio::test]
+async fn server_error_stays_queued() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.fail("/add_spent_time$", 500).await;
+
+    let (conn, id) = send_one(&gitlab.client()).await;
+
+    assert!(last_error(&conn).contains("500"));
+    assert_eq!(sync_status(&conn, id).as_deref(), Some("pendente"));
+}
+
+#[tokio::test]
+async fn rate_limit_stays_queued() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.fail("/add_spent_time$", 429).await;
+
+    let (conn, _) = send_one(&gitlab.client()).await;
+
+    assert!(last_error(&conn).contains("429"));
+}
+
+#[tokio::test]
+async fn expired_token_stays_queued() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.add_spent_time(2, 7).await;
+    let client = GitLabClient::new(&common::http(), &gitlab.url(), "token-expirado");
+
+    let (conn, _) = send_one(&client).await;
+
+    assert!(last_error(&conn).contains("Acesso negado"));
+}
+
+#[tokio::test]
+async fn timeout_stays_queued() {
+    let gitlab = MockGitLab::start().await;
+    gitlab.slow("/add_spent_time$", Duration::from_secs(2)).await;
+
+    let (conn, _) = send_one(&gitlab.client_with_timeout(Duration::from_millis(200))).await;
+
+    assert!(last_error(&conn).contains("conexão"));
+}
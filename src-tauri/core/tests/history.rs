@@ -0,0 +1,75 @@
+mod common;
+
+use common::{db, finished_session, issue, FakeClock, PROFILE_ID};
+use tracetimedev_core::editing::Span;
+use tracetimedev_core::history::{HistoryFilter, HistoryPage};
+use tracetimedev_core::Tracker;
+
+fn ids(page: &HistoryPage) -> Vec<i64> {
+    page.sessions.iter().map(|s| s.id).collect()
+}
+
+#[test]
+fn newest_sessions_come_first_with_intervals() {
+    let conn = db();
+    let old = finished_session(&conn); // 1000..1900, issue 7
+    let clock = FakeClock::at(5_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let running = tracker.start(PROFILE_ID, 1, 2, &issue(8), "Review").unwrap();
+    clock.advance(120);
+
+    let page = tracker.history(&HistoryFilter::default()).unwrap();
+
+    assert_eq!(page.total, 2);
+    assert_eq!(ids(&page), vec![running, old]);
+    let running = &page.sessions[0];
+    assert_eq!(running.total_seconds, 120);
+    assert_eq!(running.intervals.len(), 1);
+    assert_eq!(running.intervals[0].end_time, None);
+    let old = &page.sessions[1];
+    assert_eq!((old.total_seconds, old.started_at, old.ended_at), (900, Some(1_000), Some(1_900)));
+    assert_eq!(old.issue_title.as_deref(), Some("Issue 7"));
+    assert_eq!(old.sync_status.as_deref(), Some("pendente"));
+}
+
+#[test]
+fn filters_by_period_issue_and_sync_state() {
+    let conn = db();
+    let first = finished_session(&conn); // 1000..1900
+    let tracker = Tracker::with_clock(&conn, FakeClock::at(10_000));
+    let manual = tracker
+        .create_manual(PROFILE_ID, 1, 2, &issue(8), "Review", &[Span { start_time: 3_000, end_time: 3_600 }])
+        .unwrap();
+    conn.execute("UPDATE sessions SET sync_status='sincronizado' WHERE id=?1", [first]).unwrap();
+
+    let period = HistoryFilter { from: Some(1_800), to: Some(2_000), ..Default::default() };
+    assert_eq!(ids(&tracker.history(&period).unwrap()), vec![first]);
+
+    let by_issue = HistoryFilter { issue_id: Some(issue(8).id), ..Default::default() };
+    assert_eq!(ids(&tracker.history(&by_issue).unwrap()), vec![manual]);
+
+    let pending = HistoryFilter { sync_status: Some("pendente".into()), label: Some("Review".into()), ..Default::default() };
+    let page = tracker.history(&pending).unwrap();
+    assert_eq!(ids(&page), vec![manual]);
+    assert!(page.sessions[0].manual);
+
+    let other_profile = HistoryFilter { profile_id: Some(PROFILE_ID + 1), ..Default::default() };
+    assert_eq!(tracker.history(&other_profile).unwrap().total, 0);
+}
+
+#[test]
+fn pages_keep_the_total() {
+    let conn = db();
+    let tracker = Tracker::with_clock(&conn, FakeClock::at(100_000));
+    let sessions: Vec<i64> = (0..5)
+        .map(|n| {
+            let span = Span { start_time: 1_000 * (n + 1), end_time: 1_000 * (n + 1) + 60 };
+            tracker.create_manual(PROFILE_ID, 1, 2, &issue(7), "Development", &[span]).unwrap()
+        })
+        .collect();
+
+    let page = tracker.history(&HistoryFilter { offset: 2, limit: Some(2), ..Default::default() }).unwrap();
+
+    assert_eq!(page.total, 5);
+    assert_eq!(ids(&page), vec![sessions[2], sessions[1]]);
+}
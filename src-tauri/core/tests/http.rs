@@ -0,0 +1,59 @@
+//! Configuração de rede do cliente compartilhado: certificados extras, mTLS e proxy
+
+mod common;
+
+use common::mock_gitlab::{MockGitLab, TOKEN, USER_ID};
+use tracetimedev_core::backend::TrackerBackend;
+use tracetimedev_core::gitlab::GitLabClient;
+use tracetimedev_core::http::{self, HttpSettings};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn loads_internal_ca_and_client_certificate() {
+    let settings = HttpSettings {
+        ca_certs: vec![fixture("ca.pem")],
+        client_cert: Some(fixture("client.crt")),
+        client_key: Some(fixture("client.key")),
+        ..Default::default()
+    };
+
+    assert!(http::client(&settings).is_ok());
+}
+
+#[test]
+fn client_certificate_without_key_is_rejected() {
+    let settings = HttpSettings { client_cert: Some(fixture("client.crt")), ..Default::default() };
+
+    assert!(http::client(&settings).is_err());
+}
+
+#[tokio::test]
+async fn requests_go_through_the_proxy() {
+    // o servidor de teste faz o papel do proxy: recebe o pedido para o host interno
+    let proxy = MockGitLab::start().await;
+    let settings = HttpSettings { proxy_url: Some(proxy.url()), ..Default::default() };
+    let client = GitLabClient::new(&http::client(&settings).unwrap(), "http://gitlab.interno.invalid", TOKEN);
+
+    let user = client.current_user().await.unwrap();
+
+    assert_eq!(user.id, USER_ID);
+    let requests = proxy.server.received_requests().await.unwrap();
+    assert_eq!(requests[0].headers.get("host").unwrap(), "gitlab.interno.invalid");
+}
+
+#[tokio::test]
+async fn no_proxy_hosts_are_reached_directly() {
+    let gitlab = MockGitLab::start().await;
+    let settings = HttpSettings {
+        // porta fechada: se o pedido passasse pelo proxy, falharia
+        proxy_url: Some("http://127.0.0.1:9".into()),
+        no_proxy: "localhost, 127.0.0.1".into(),
+        ..Default::default()
+    };
+    let client = GitLabClient::new(&http::client(&settings).unwrap(), &gitlab.url(), TOKEN);
+
+    assert_eq!(client.current_user().await.unwrap().id, USER_ID);
+}
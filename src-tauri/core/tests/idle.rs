@@ -0,0 +1,159 @@
+mod common;
+
+use common::{db, issue, FakeClock, PROFILE_ID};
+use std::cell::Cell;
+use tracetimedev_core::idle::{IdleDecision, IdleEvent};
+use tracetimedev_core::state::TimerState;
+use tracetimedev_core::sync::outbox_status;
+use tracetimedev_core::Tracker;
+
+const THRESHOLD: i64 = 600;
+
+/// Tempo ocioso informado pelo teste
+struct FakeIdle(Cell<i64>);
+
+/// Trabalha 30 min, fica 20 min fora e volta: retorna (sessão, bloco ausente)
+fn lunch(tracker: &Tracker<&FakeClock>, clock: &FakeClock, idle: &FakeIdle) -> (i64, i64) {
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(1_800 + THRESHOLD);
+    idle.0.set(THRESHOLD);
+    let Some(IdleEvent::Started(block)) = tracker.check_idle(idle.0.get(), THRESHOLD).unwrap() else {
+        panic!("ausência não detectada");
+    };
+    assert_eq!((block.start_time, block.end_time), (2_800, None));
+
+    clock.advance(600);
+    idle.0.set(5);
+    let Some(IdleEvent::Ended(block)) = tracker.check_idle(idle.0.get(), THRESHOLD).unwrap() else {
+        panic!("volta não detectada");
+    };
+    assert_eq!((block.start_time, block.end_time, block.seconds), (2_800, Some(3_995), 1_195));
+    (id, block.interval_id)
+}
+
+#[test]
+fn idle_block_is_split_from_the_running_interval() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let idle = FakeIdle(Cell::new(0));
+
+    let (id, block) = lunch(&tracker, &clock, &idle);
+
+    // o timer continua rodando num intervalo novo a partir da volta
+    let intervals = tracker.intervals(id).unwrap();
+    assert_eq!(intervals.len(), 3);
+    assert_eq!((intervals[2].start_time, intervals[2].end_time), (3_995, None));
+    assert!(matches!(tracker.state().unwrap(), TimerState::Running { session_id, .. } if session_id == id));
+    assert_eq!(tracker.pending_idle().unwrap().unwrap().interval_id, block);
+}
+
+#[test]
+fn nothing_happens_below_the_threshold_or_when_paused() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let idle = FakeIdle(Cell::new(THRESHOLD - 1));
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(3_600);
+
+    assert_eq!(tracker.check_idle(idle.0.get(), THRESHOLD).unwrap(), None);
+    assert_eq!(tracker.check_idle(idle.0.get(), 0).unwrap(), None);
+
+    tracker.pause(id).unwrap();
+    idle.0.set(THRESHOLD * 2);
+    assert_eq!(tracker.check_idle(idle.0.get(), THRESHOLD).unwrap(), None);
+}
+
+#[test]
+fn discarding_removes_the_idle_time() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let idle = FakeIdle(Cell::new(0));
+    let (id, block) = lunch(&tracker, &clock, &idle);
+
+    tracker.resolve_idle(block, &IdleDecision::Discard).unwrap();
+
+    // 30 min antes + 5 s desde a volta
+    assert_eq!(tracker.session_seconds(id).unwrap(), 1_805);
+    assert!(tracker.pending_idle().unwrap().is_none());
+}
+
+#[test]
+fn keeping_counts_the_idle_time() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let idle = FakeIdle(Cell::new(0));
+    let (id, block) = lunch(&tracker, &clock, &idle);
+
+    tracker.resolve_idle(block, &IdleDecision::Keep).unwrap();
+
+    assert_eq!(tracker.session_seconds(id).unwrap(), 3_000);
+    assert!(tracker.pending_idle().unwrap().is_none());
+}
+
+#[test]
+fn reassigning_moves_the_idle_time_to_another_issue() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let idle = FakeIdle(Cell::new(0));
+    let (id, block) = lunch(&tracker, &clock, &idle);
+
+    let decision = IdleDecision::Reassign {
+        profile_id: PROFILE_ID,
+        group_id: 1,
+        project_id: 2,
+        issue: issue(9),
+        label: "Reunião".into(),
+    };
+    let moved = tracker.resolve_idle(block, &decision).unwrap().unwrap();
+
+    assert_eq!(tracker.session_seconds(id).unwrap(), 1_805);
+    assert_eq!(tracker.session_seconds(moved).unwrap(), 1_195);
+    // a sessão nova já vai para a fila; a que está rodando só quando finalizar
+    assert_eq!(outbox_status(&conn).unwrap().pending, 1);
+}
+
+#[test]
+fn deciding_before_coming_back_closes_the_block_now() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let idle = FakeIdle(Cell::new(THRESHOLD));
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(THRESHOLD + 100);
+    let Some(IdleEvent::Started(block)) = tracker.check_idle(idle.0.get(), THRESHOLD).unwrap() else {
+        panic!("ausência não detectada");
+    };
+    clock.advance(50);
+
+    tracker.resolve_idle(block.interval_id, &IdleDecision::Discard).unwrap();
+
+    assert_eq!(tracker.session_seconds(id).unwrap(), 100);
+    assert!(matches!(tracker.state().unwrap(), TimerState::Running { .. }));
+}
+
+#[test]
+fn undecided_idle_time_is_not_sent_when_stopping() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let idle = FakeIdle(Cell::new(0));
+    let (id, block) = lunch(&tracker, &clock, &idle);
+
+    tracker.stop(id).unwrap();
+    assert_eq!(queued_seconds(&conn, id), Some(1_805));
+
+    // a decisão continua pendente depois de finalizar; manter coloca o bloco na fila
+    assert_eq!(tracker.pending_idle().unwrap().unwrap().interval_id, block);
+    tracker.resolve_idle(block, &IdleDecision::Keep).unwrap();
+    assert_eq!(queued_seconds(&conn, id), Some(3_000));
+}
+
+fn queued_seconds(conn: &rusqlite::Connection, session_id: i64) -> Option<i64> {
+    conn.query_row("SELECT seconds FROM sync_outbox WHERE session_id=?1", [session_id], |row| row.get(0))
+        .ok()
+}
@@ -0,0 +1,176 @@
+//! Login OAuth com PKCE contra um servidor de autorização de teste
+
+mod common;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use common::http;
+use base64::Engine;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+use tracetimedev_core::backend::TrackerBackend;
+use tracetimedev_core::gitlab::GitLabClient;
+use tracetimedev_core::oauth::{self, Loopback, OAuthSession, OAuthTokens};
+use tracetimedev_core::Error;
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const CLIENT_ID: &str = "tracetimedev-app";
+
+/// Servidor que só aceita `new-access` em `/user` e troca `old-refresh` por tokens novos
+async fn authorization_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v4/user"))
+        .and(header("Authorization", "Bearer new-access"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": 42, "username": "dev", "name": "Dev" })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v4/user"))
+        .respond_with(ResponseTemplate::new(401).set_body_json(json!({ "error": "invalid_token" })))
+        .with_priority(10)
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn mount_refresh(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/oauth/token"))
+        .and(body_string_contains("grant_type=refresh_token"))
+        .and(body_string_contains("refresh_token=old-refresh"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "new-access",
+            "refresh_token": "new-refresh",
+            "token_type": "Bearer",
+            "expires_in": 7200,
+            "created_at": 1_700_000_000
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+/// Sessão com os tokens dados; os renovados vão para `saved`
+fn session(server: &MockServer, expires_at: Option<i64>, saved: &Arc<Mutex<Vec<OAuthTokens>>>) -> Arc<OAuthSession> {
+    let saved = saved.clone();
+    let tokens = OAuthTokens { access_token: "old-access".into(), refresh_token: "old-refresh".into(), expires_at };
+    Arc::new(OAuthSession::new(
+        &http(),
+        &server.uri(),
+        CLIENT_ID,
+        tokens,
+        Box::new(move |tokens| {
+            saved.lock().unwrap().push(tokens.clone());
+            Ok(())
+        }),
+    ))
+}
+
+#[tokio::test]
+async fn login_with_pkce_through_loopback() {
+    let server = authorization_server().await;
+    Mock::given(method("POST"))
+        .and(path("/oauth/token"))
+        .and(body_string_contains("grant_type=authorization_code"))
+        .and(body_string_contains("code=codigo-123"))
+        .and(body_string_contains("code_verifier="))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "new-access",
+            "refresh_token": "new-refresh",
+            "expires_in": 7200,
+            "created_at": 1_700_000_000
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let loopback = Loopback::bind().await.unwrap();
+    let request = oauth::authorization(&server.uri(), CLIENT_ID, &loopback.redirect_uri()).unwrap();
+
+    // o "navegador" volta para o redirect com o código
+    let callback = format!("{}?code=codigo-123&state={}", request.redirect_uri, request.state);
+    let (code, page) = tokio::join!(loopback.wait_code(&request.state), async {
+        http().get(&callback).send().await.unwrap().text().await.unwrap()
+    });
+    assert!(page.contains("Login concluído"), "{}", page);
+    let tokens = oauth::exchange_code(&http(), &server.uri(), CLIENT_ID, &code.unwrap(), &request)
+        .await
+        .unwrap();
+
+    assert_eq!(tokens.access_token, "new-access");
+    assert_eq!(tokens.expires_at, Some(1_700_007_200));
+    // o verifier enviado é o que gerou o challenge da URL
+    let requests = server.received_requests().await.unwrap();
+    let body: String = String::from_utf8(requests.last().unwrap().body.clone()).unwrap();
+    let verifier = body.split('&').find_map(|pair| pair.strip_prefix("code_verifier=")).unwrap();
+    let challenge = request.url.query_pairs().find(|(k, _)| k == "code_challenge").unwrap().1;
+    let expected = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    assert_eq!(challenge, expected);
+}
+
+#[tokio::test]
+async fn callback_with_other_state_is_ignored() {
+    let loopback = Loopback::bind().await.unwrap();
+    let forged = format!("{}?code=forjado&state=forjado", loopback.redirect_uri());
+    let callback = format!("{}?code=codigo-123&state=esperado", loopback.redirect_uri());
+
+    let (code, forged) = tokio::join!(loopback.wait_code("esperado"), async {
+        let forged = http().get(&forged).send().await.unwrap().status();
+        http().get(&callback).send().await.unwrap();
+        forged
+    });
+
+    assert_eq!(forged, 400);
+    assert_eq!(code.unwrap(), "codigo-123");
+}
+
+#[tokio::test]
+async fn refreshes_once_on_401_and_saves_new_tokens() {
+    let server = authorization_server().await;
+    mount_refresh(&server).await;
+    let saved = Arc::new(Mutex::new(Vec::new()));
+    let session = session(&server, None, &saved);
+    // clientes diferentes do mesmo perfil (comando, cache, envio) dividem a sessão
+    let command = GitLabClient::with_oauth(&http(), session.clone());
+    let worker = GitLabClient::with_oauth(&http(), session);
+
+    // duas chamadas recebendo 401 ao mesmo tempo renovam uma vez só
+    let (first, second) = tokio::join!(command.current_user(), worker.current_user());
+
+    assert_eq!(first.unwrap().id, 42);
+    assert_eq!(second.unwrap().id, 42);
+    let saved = saved.lock().unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!((saved[0].access_token.as_str(), saved[0].refresh_token.as_str()), ("new-access", "new-refresh"));
+}
+
+#[tokio::test]
+async fn expired_access_token_is_renewed_before_the_call() {
+    let server = authorization_server().await;
+    mount_refresh(&server).await;
+    let saved = Arc::new(Mutex::new(Vec::new()));
+    let client = GitLabClient::with_oauth(&http(), session(&server, Some(0), &saved));
+
+    client.current_user().await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests.iter().all(|req| req.headers.get("authorization").is_none_or(|v| v != "Bearer old-access")));
+}
+
+#[tokio::test]
+async fn revoked_refresh_token_asks_for_new_login() {
+    let server = authorization_server().await;
+    Mock::given(method("POST"))
+        .and(path("/oauth/token"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({ "error": "invalid_grant" })))
+        .mount(&server)
+        .await;
+    let saved = Arc::new(Mutex::new(Vec::new()));
+    let client = GitLabClient::with_oauth(&http(), session(&server, None, &saved));
+
+    let err = client.current_user().await.unwrap_err();
+
+    assert!(matches!(err, Error::Auth(ref msg) if msg.contains("entre novamente")), "{:?}", err);
+    assert!(saved.lock().unwrap().is_empty());
+}
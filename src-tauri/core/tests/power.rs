@@ -0,0 +1,90 @@
+mod common;
+
+use common::{db, issue, FakeClock, PROFILE_ID};
+use std::collections::VecDeque;
+use tracetimedev_core::power::{PowerAction, PowerEvent, PowerSource};
+use tracetimedev_core::state::TimerState;
+use tracetimedev_core::Tracker;
+
+/// Eventos fixos, na ordem em que o sistema os mandaria
+struct FakePower(VecDeque<PowerEvent>);
+
+impl FakePower {
+    fn new(events: &[PowerEvent]) -> Self {
+        FakePower(events.iter().copied().collect())
+    }
+}
+
+impl PowerSource for FakePower {
+    fn next_event(&mut self) -> Option<PowerEvent> {
+        self.0.pop_front()
+    }
+}
+
+/// Trata cada evento com o relógio avançando `gap` segundos entre eles
+fn replay(
+    tracker: &Tracker<&FakeClock>,
+    clock: &FakeClock,
+    source: &mut dyn PowerSource,
+    gap: i64,
+    auto_resume: bool,
+) -> Vec<Option<PowerAction>> {
+    let mut actions = Vec::new();
+    while let Some(event) = source.next_event() {
+        clock.advance(gap);
+        actions.push(tracker.handle_power(event, auto_resume).unwrap());
+    }
+    actions
+}
+
+#[test]
+fn suspending_closes_the_open_interval() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+
+    let mut night = FakePower::new(&[PowerEvent::Sleep, PowerEvent::Wake]);
+    let actions = replay(&tracker, &clock, &mut night, 28_800, false);
+
+    assert_eq!(actions, vec![Some(PowerAction::Paused(id)), None]);
+    assert_eq!(tracker.session_seconds(id).unwrap(), 28_800);
+    assert_eq!(tracker.state().unwrap(), TimerState::Paused { session_id: id });
+}
+
+#[test]
+fn unlocking_resumes_only_when_enabled() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+
+    let mut coffee = FakePower::new(&[PowerEvent::Lock, PowerEvent::Unlock]);
+    assert_eq!(replay(&tracker, &clock, &mut coffee, 600, false), vec![Some(PowerAction::Paused(id)), None]);
+
+    tracker.resume(id).unwrap();
+    let mut coffee = FakePower::new(&[PowerEvent::Sleep, PowerEvent::Wake, PowerEvent::Lock, PowerEvent::Unlock]);
+    let actions = replay(&tracker, &clock, &mut coffee, 600, true);
+
+    assert_eq!(actions, vec![Some(PowerAction::Paused(id)), None, None, Some(PowerAction::Resumed(id))]);
+    assert!(matches!(tracker.state().unwrap(), TimerState::Running { session_id, .. } if session_id == id));
+    // 600 s antes do primeiro bloqueio e 600 s antes da suspensão
+    assert_eq!(tracker.session_seconds(id).unwrap(), 1_200);
+}
+
+#[test]
+fn manual_pause_and_shutdown_are_not_resumed() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    tracker.pause(id).unwrap();
+
+    let mut lunch = FakePower::new(&[PowerEvent::Lock, PowerEvent::Unlock]);
+    assert_eq!(replay(&tracker, &clock, &mut lunch, 600, true), vec![None, None]);
+
+    tracker.resume(id).unwrap();
+    let mut reboot = FakePower::new(&[PowerEvent::Shutdown, PowerEvent::Unlock]);
+    assert_eq!(replay(&tracker, &clock, &mut reboot, 600, true), vec![Some(PowerAction::Paused(id)), None]);
+    assert_eq!(tracker.state().unwrap(), TimerState::Paused { session_id: id });
+}
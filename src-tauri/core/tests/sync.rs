@@ -0,0 +1,254 @@
+mod common;
+
+use common::{db, finished_session, PROFILE_ID};
+use rusqlite::Connection;
+use std::sync::{Arc, Mutex};
+use tracetimedev_core::backend::{Fetched, TrackerBackend};
+use tracetimedev_core::editing::Span;
+use tracetimedev_core::models::{Group, Issue, IssueFilter, Note, Project, TimeStats, TokenInfo, User};
+use tracetimedev_core::sync::{backfill_issue_refs, outbox_status, send_due};
+use tracetimedev_core::{Error, Result, Tracker};
+
+/// Chamado no meio de um lançamento, enquanto ele está em voo
+type DuringSend = Box<dyn Fn() + Send + Sync>;
+
+/// Rastreador em memória que registra os lançamentos recebidos
+#[derive(Default)]
+struct FakeGitLab {
+    spent: Mutex<Vec<(i64, i64, i64, String)>>,
+    offline: bool,
+    during_send: Option<DuringSend>,
+}
+
+impl TrackerBackend for FakeGitLab {
+    fn base_url(&self) -> &str {
+        "https://gitlab.example.com"
+    }
+
+    async fn current_user(&self) -> Result<User> {
+        Err(Error::NotFound("sem usuário".into()))
+    }
+
+    async fn token_info(&self) -> Result<Option<TokenInfo>> {
+        Ok(None)
+    }
+
+    async fn containers(&self, _limit: usize, _etag: Option<&str>) -> Result<Fetched<Group>> {
+        unimplemented!()
+    }
+
+    async fn projects(&self, _container_id: i64, _limit: usize, _etag: Option<&str>) -> Result<Fetched<Project>> {
+        unimplemented!()
+    }
+
+    async fn issues(
+        &self,
+        _project_id: i64,
+        _filter: &IssueFilter,
+        _user_id: i64,
+        _limit: usize,
+        _etag: Option<&str>,
+    ) -> Result<Fetched<Issue>> {
+        unimplemented!()
+    }
+
+    async fn find_issue(&self, project_id: i64, issue_id: i64) -> Result<Issue> {
+        Ok(Issue {
+            id: issue_id,
+            iid: issue_id - 1000,
+            project_id,
+            title: "Resolvida pela API".into(),
+            state: "opened".into(),
+            web_url: "https://gitlab.example.com/g/p/-/issues/9".into(),
+            labels: Vec::new(),
+            milestone: None,
+            assignees: Vec::new(),
+            time_stats: None,
+        })
+    }
+
+    async fn log_time(&self, project_id: i64, issue_iid: i64, seconds: i64, summary: &str) -> Result<TimeStats> {
+        if let Some(during_send) = &self.during_send {
+            during_send();
+        }
+        if self.offline {
+            return Err(Error::Network("connection refused".into()));
+        }
+        self.spent
+            .lock()
+            .unwrap()
+            .push((project_id, issue_iid, seconds, summary.to_string()));
+        Ok(TimeStats {
+            time_estimate: 0,
+            total_time_spent: 900,
+            human_time_estimate: None,
+            human_total_time_spent: Some("15m".into()),
+        })
+    }
+
+    async fn time_stats(&self, _project_id: i64, _issue_iid: i64) -> Result<TimeStats> {
+        unimplemented!()
+    }
+
+    async fn issue_notes(&self, _project_id: i64, _issue_iid: i64) -> Result<Vec<Note>> {
+        Ok(Vec::new())
+    }
+}
+
+fn sync_status(conn: &Mutex<Connection>, session_id: i64) -> Option<String> {
+    conn.lock()
+        .unwrap()
+        .query_row("SELECT sync_status FROM sessions WHERE id=?1", [session_id], |row| row.get(0))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn sends_finished_sessions_to_gitlab() {
+    let conn = db();
+    let id = finished_session(&conn);
+    let conn = Mutex::new(conn);
+    let gitlab = FakeGitLab::default();
+
+    assert_eq!(send_due(&conn, PROFILE_ID, &gitlab).await.unwrap(), 1);
+
+    assert_eq!(
+        *gitlab.spent.lock().unwrap(),
+        vec![(2, 7, 900, "Development".to_string())]
+    );
+    assert_eq!(sync_status(&conn, id).as_deref(), Some("sincronizado"));
+    assert_eq!(outbox_status(&conn.lock().unwrap()).unwrap().pending, 0);
+
+    // nada mais a enviar
+    assert_eq!(send_due(&conn, PROFILE_ID, &gitlab).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn failed_send_stays_queued() {
+    let conn = db();
+    let id = finished_session(&conn);
+    let conn = Mutex::new(conn);
+    let gitlab = FakeGitLab { offline: true, ..Default::default() };
+
+    assert_eq!(send_due(&conn, PROFILE_ID, &gitlab).await.unwrap(), 1);
+
+    let status = outbox_status(&conn.lock().unwrap()).unwrap();
+    assert_eq!(status.pending, 1);
+    assert!(status.last_error.unwrap().contains("connection refused"));
+    assert_eq!(sync_status(&conn, id).as_deref(), Some("pendente"));
+
+    // a próxima tentativa só vence depois do backoff
+    assert_eq!(send_due(&conn, PROFILE_ID, &gitlab).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn stopping_twice_sends_once() {
+    let conn = db();
+    let id = finished_session(&conn);
+    assert!(matches!(Tracker::new(&conn).stop(id), Err(Error::InvalidState(_))));
+    let conn = Mutex::new(conn);
+    let gitlab = FakeGitLab::default();
+
+    send_due(&conn, PROFILE_ID, &gitlab).await.unwrap();
+    assert!(Tracker::new(&conn.lock().unwrap()).stop(id).is_err());
+    send_due(&conn, PROFILE_ID, &gitlab).await.unwrap();
+
+    assert_eq!(gitlab.spent.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn backfills_issue_of_old_sessions() {
+    let conn = db();
+    conn.execute(
+        "INSERT INTO sessions (profile_id, group_id, project_id, issue_id, label, status) VALUES (1, 1, 2, 1009, 'Dev', 'finalizado')",
+        [],
+    )
+    .unwrap();
+    let conn = Mutex::new(conn);
+
+    backfill_issue_refs(&conn, PROFILE_ID, &FakeGitLab::default()).await.unwrap();
+
+    let (iid, title): (i64, String) = conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT issue_iid, issue_title FROM sessions WHERE issue_id=1009", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .unwrap();
+    assert_eq!((iid, title.as_str()), (9, "Resolvida pela API"));
+}
+
+#[tokio::test]
+async fn sends_only_the_profile_sessions() {
+    let conn = db();
+    let id = finished_session(&conn);
+    let conn = Mutex::new(conn);
+    let other = FakeGitLab::default();
+
+    assert_eq!(send_due(&conn, PROFILE_ID + 1, &other).await.unwrap(), 0);
+    assert!(other.spent.lock().unwrap().is_empty());
+    assert_eq!(sync_status(&conn, id).as_deref(), Some("pendente"));
+}
+
+#[tokio::test]
+async fn edited_synced_session_sends_only_the_difference() {
+    let conn = db();
+    let id = finished_session(&conn);
+    let conn = Mutex::new(conn);
+    let gitlab = FakeGitLab::default();
+    send_due(&conn, PROFILE_ID, &gitlab).await.unwrap();
+
+    {
+        let conn = conn.lock().unwrap();
+        let tracker = Tracker::new(&conn);
+        let interval = tracker.intervals(id).unwrap()[0].id;
+        tracker.update_interval(interval, &Span { start_time: 1_000, end_time: 1_600 }).unwrap();
+    }
+    assert_eq!(sync_status(&conn, id).as_deref(), Some("alterado"));
+
+    send_due(&conn, PROFILE_ID, &gitlab).await.unwrap();
+
+    let spent: Vec<i64> = gitlab.spent.lock().unwrap().iter().map(|(_, _, seconds, _)| *seconds).collect();
+    assert_eq!(spent, vec![900, -300]);
+    assert_eq!(sync_status(&conn, id).as_deref(), Some("sincronizado"));
+    assert_eq!(outbox_status(&conn.lock().unwrap()).unwrap().pending, 0);
+}
+
+#[tokio::test]
+async fn sessions_in_flight_cannot_be_edited() {
+    let conn = Arc::new(Mutex::new(db()));
+    let (first, second) = {
+        let conn = conn.lock().unwrap();
+        (finished_session(&conn), finished_session(&conn))
+    };
+    let refused = Arc::new(Mutex::new(Vec::new()));
+    let api = FakeGitLab {
+        during_send: Some(Box::new({
+            let (conn, refused) = (conn.clone(), refused.clone());
+            move || {
+                // só no primeiro lançamento: o da sessão `first`
+                if !refused.lock().unwrap().is_empty() {
+                    return;
+                }
+                let conn = conn.lock().unwrap();
+                let tracker = Tracker::new(&conn);
+                let interval = tracker.intervals(first).unwrap()[0].id;
+                refused.lock().unwrap().push((
+                    tracker.merge_sessions(second, first).is_err(),
+                    tracker.delete_interval(interval).is_err(),
+                    tracker.update_interval(interval, &Span { start_time: 1_000, end_time: 1_600 }).is_err(),
+                    tracker.split_session(first, 1_300).is_err(),
+                ));
+            }
+        })),
+        ..Default::default()
+    };
+
+    send_due(&conn, PROFILE_ID, &api).await.unwrap();
+
+    assert_eq!(*refused.lock().unwrap(), vec![(true, true, true, true)]);
+    // depois do envio as duas podem ser juntadas e nada é mandado de novo
+    let conn = conn.lock().unwrap();
+    Tracker::new(&conn).merge_sessions(second, first).unwrap();
+    assert_eq!(outbox_status(&conn).unwrap().pending, 0);
+    assert_eq!(api.spent.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,300 @@
+mod common;
+
+use common::{db, issue, FakeClock, PROFILE_ID};
+use tracetimedev_core::state::{SwitchAction, TimerState};
+use tracetimedev_core::sync::outbox_status;
+use tracetimedev_core::{Error, Tracker};
+
+fn status(conn: &rusqlite::Connection, session_id: i64) -> String {
+    conn.query_row("SELECT status FROM sessions WHERE id=?1", [session_id], |row| row.get(0))
+        .unwrap()
+}
+
+fn open_intervals(conn: &rusqlite::Connection, session_id: i64) -> i64 {
+    conn.query_row(
+        "SELECT COUNT(*) FROM session_intervals WHERE session_id=?1 AND end_time IS NULL",
+        [session_id],
+        |row| row.get(0),
+    )
+    .unwrap()
+}
+
+#[test]
+fn start_opens_a_running_session() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+
+    assert_eq!(status(&conn, id), "runner");
+    assert_eq!(open_intervals(&conn, id), 1);
+    assert_eq!(tracker.running_session().unwrap(), Some(id));
+    let iid: i64 = conn
+        .query_row("SELECT issue_iid FROM sessions WHERE id=?1", [id], |row| row.get(0))
+        .unwrap();
+    assert_eq!(iid, 7);
+}
+
+#[test]
+fn cannot_start_while_another_timer_runs() {
+    let conn = db();
+    let tracker = Tracker::with_clock(&conn, FakeClock::at(1_000));
+    tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+
+    let err = tracker.start(PROFILE_ID, 1, 2, &issue(8), "Review").unwrap_err();
+    assert!(matches!(err, Error::InvalidState(_)));
+}
+
+#[test]
+fn open_interval_counts_until_now() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+
+    clock.advance(90);
+    assert_eq!(tracker.session_seconds(id).unwrap(), 90);
+    assert_eq!(tracker.session_time(id).unwrap(), "1m30s");
+}
+
+#[test]
+fn paused_time_is_not_counted() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+
+    clock.advance(600);
+    tracker.pause(id).unwrap();
+    assert_eq!(status(&conn, id), "pause");
+    assert_eq!(open_intervals(&conn, id), 0);
+    assert_eq!(tracker.running_session().unwrap(), None);
+
+    clock.advance(400);
+    assert_eq!(tracker.session_seconds(id).unwrap(), 600);
+
+    tracker.resume(id).unwrap();
+    assert_eq!(status(&conn, id), "runner");
+    clock.advance(300);
+    assert_eq!(tracker.session_seconds(id).unwrap(), 900);
+}
+
+#[test]
+fn stop_closes_the_session_and_queues_it() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+
+    clock.advance(600);
+    tracker.pause(id).unwrap();
+    clock.advance(60);
+    tracker.resume(id).unwrap();
+    clock.advance(500);
+    tracker.stop(id).unwrap();
+    clock.advance(1_000);
+
+    assert_eq!(status(&conn, id), "finalizado");
+    assert_eq!(tracker.session_seconds(id).unwrap(), 1_100);
+    assert_eq!(outbox_status(&conn).unwrap().pending, 1);
+    let duration: String = conn
+        .query_row("SELECT duration FROM sync_outbox WHERE session_id=?1", [id], |row| row.get(0))
+        .unwrap();
+    assert_eq!(duration, "18m20s");
+
+    // uma nova sessão pode começar depois de finalizar
+    assert!(tracker.start(PROFILE_ID, 1, 2, &issue(8), "Review").is_ok());
+}
+
+#[test]
+fn last_session_is_read_without_pausing() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    assert!(tracker.last_session().unwrap().is_none());
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(120);
+
+    let (last_id, profile_id, group_id, project_id, issue_id, label, status, preview) =
+        tracker.last_session().unwrap().unwrap();
+
+    assert_eq!((last_id, profile_id, group_id, project_id, issue_id), (id, PROFILE_ID, 1, 2, 1007));
+    assert_eq!(label, "Development");
+    assert_eq!(status, "runner");
+    assert_eq!(preview, "2m");
+    // reabrir a janela não mexe no timer
+    assert!(matches!(tracker.state().unwrap(), TimerState::Running { session_id, .. } if session_id == id));
+
+    tracker.stop(id).unwrap();
+    assert!(tracker.last_session().unwrap().is_none());
+}
+
+#[test]
+fn orphaned_interval_closes_at_last_heartbeat() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+
+    clock.advance(300);
+    tracker.heartbeat().unwrap();
+    // app fechado sem pausar; reaberto muito depois
+    clock.advance(10_000);
+
+    assert_eq!(tracker.close_orphaned_intervals().unwrap(), 1);
+    assert_eq!(status(&conn, id), "pause");
+    assert_eq!(tracker.session_seconds(id).unwrap(), 300);
+
+    let recovery = tracker.pending_recovery().unwrap().unwrap();
+    assert_eq!((recovery.session_id, recovery.seconds, recovery.minutes), (id, 300, 5));
+}
+
+#[test]
+fn discarding_recovered_time_removes_it() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(300);
+    tracker.heartbeat().unwrap();
+    tracker.close_orphaned_intervals().unwrap();
+
+    tracker.resolve_recovery(id, false).unwrap();
+
+    assert!(tracker.pending_recovery().unwrap().is_none());
+    assert_eq!(tracker.session_seconds(id).unwrap(), 0);
+}
+
+#[test]
+fn recovered_time_waits_for_the_decision_before_syncing() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(300);
+    tracker.heartbeat().unwrap();
+    tracker.close_orphaned_intervals().unwrap();
+    tracker.resume(id).unwrap();
+    clock.advance(120);
+    tracker.stop(id).unwrap();
+    let outbox = || {
+        conn.query_row("SELECT seconds FROM sync_outbox WHERE session_id=?1", [id], |row| row.get::<_, i64>(0))
+            .unwrap()
+    };
+    assert_eq!(outbox(), 120);
+
+    tracker.resolve_recovery(id, false).unwrap();
+
+    assert_eq!(outbox(), 120);
+    assert_eq!(tracker.session_seconds(id).unwrap(), 120);
+}
+
+#[test]
+fn keeping_recovered_time_queues_it() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(300);
+    tracker.heartbeat().unwrap();
+    tracker.close_orphaned_intervals().unwrap();
+    tracker.stop(id).unwrap();
+    assert_eq!(outbox_status(&conn).unwrap().pending, 0);
+
+    tracker.resolve_recovery(id, true).unwrap();
+
+    let seconds: i64 = conn
+        .query_row("SELECT seconds FROM sync_outbox WHERE session_id=?1", [id], |row| row.get(0))
+        .unwrap();
+    assert_eq!(seconds, 300);
+}
+
+#[test]
+fn snapshot_follows_the_current_session() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    assert_eq!(tracker.snapshot().unwrap().status, "idle");
+
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(300);
+    tracker.pause(id).unwrap();
+    clock.advance(60);
+    tracker.resume(id).unwrap();
+
+    let snapshot = tracker.snapshot().unwrap();
+    assert_eq!((snapshot.session_id, snapshot.status.as_str()), (Some(id), "runner"));
+    assert_eq!(snapshot.issue_title.as_deref(), Some("Issue 7"));
+    assert_eq!(snapshot.elapsed(1_400), 340);
+
+    tracker.stop(id).unwrap();
+    assert_eq!(tracker.snapshot().unwrap().session_id, None);
+}
+
+#[test]
+fn invalid_transitions_are_rejected() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let first = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(60);
+    tracker.pause(first).unwrap();
+    let second = tracker.start(PROFILE_ID, 1, 2, &issue(8), "Review").unwrap();
+
+    // a primeira não volta a rodar enquanto a segunda roda
+    assert!(matches!(tracker.resume(first), Err(Error::InvalidState(_))));
+    assert!(matches!(tracker.pause(first), Err(Error::InvalidState(_))));
+    assert!(matches!(tracker.resume(second), Err(Error::InvalidState(_))));
+
+    tracker.stop(second).unwrap();
+    assert!(matches!(tracker.pause(second), Err(Error::InvalidState(_))));
+    assert!(matches!(tracker.resume(second), Err(Error::InvalidState(_))));
+    assert!(matches!(tracker.stop(second), Err(Error::InvalidState(_))));
+    assert!(matches!(tracker.pause(999), Err(Error::NotFound(_))));
+    assert_eq!(tracker.state().unwrap(), TimerState::Paused { session_id: first });
+}
+
+#[test]
+fn state_is_rehydrated_from_the_database() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    assert_eq!(tracker.state().unwrap(), TimerState::Idle);
+    let id = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    let interval_id = tracker.intervals(id).unwrap()[0].id;
+    assert_eq!(tracker.state().unwrap(), TimerState::Running { session_id: id, interval_id });
+
+    // reinício do app com o timer rodando
+    let restarted = Tracker::with_clock(&conn, &clock);
+    restarted.close_orphaned_intervals().unwrap();
+
+    assert_eq!(restarted.state().unwrap(), TimerState::Paused { session_id: id });
+    restarted.resume(id).unwrap();
+    assert!(matches!(restarted.state().unwrap(), TimerState::Running { session_id, .. } if session_id == id));
+}
+
+#[test]
+fn switching_task_finishes_or_pauses_the_current_session() {
+    let conn = db();
+    let clock = FakeClock::at(1_000);
+    let tracker = Tracker::with_clock(&conn, &clock);
+    let first = tracker.start(PROFILE_ID, 1, 2, &issue(7), "Development").unwrap();
+    clock.advance(600);
+
+    let second = tracker.switch_task(PROFILE_ID, 1, 2, &issue(8), "Review", SwitchAction::Pause).unwrap();
+
+    assert_eq!(status(&conn, first), "pause");
+    assert_eq!(tracker.session_seconds(first).unwrap(), 600);
+    assert!(matches!(tracker.state().unwrap(), TimerState::Running { session_id, .. } if session_id == second));
+
+    clock.advance(300);
+    let third = tracker.switch_task(PROFILE_ID, 1, 2, &issue(9), "Development", SwitchAction::Stop).unwrap();
+
+    assert_eq!(status(&conn, second), "finalizado");
+    assert_eq!(tracker.session_seconds(second).unwrap(), 300);
+    assert_eq!(outbox_status(&conn).unwrap().pending, 1);
+    assert_eq!(status(&conn, third), "runner");
+    // a primeira continua pausada: só a sessão atual é trocada
+    assert_eq!(status(&conn, first), "pause");
+}
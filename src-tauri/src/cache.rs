@@ -0,0 +1,11 @@
+
+use crate::AppState;
+use tauri::State;
+use tracetimedev_core::{cache, Result};
+
+/// Apaga todo o cache de grupos, projetos e issues
+#[tauri::command]
+pub fn gitlab_cache_purge(state: State<AppState>) -> Result<()> {
+    let conn = state.conn.lock()?;
+    cache::purge(&conn)
+}
@@ -1,38 +1,164 @@
+use crate::gitlab::{HttpClient, OAuthSessions};
 use crate::AppState;
-use rusqlite::params;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracetimedev_core::config::{self, Profile, TokenExpiry};
+use tracetimedev_core::http::{self, HttpSettings};
+use tracetimedev_core::secrets::{Cached, EncryptedFile, KeySource, Keyring, SecretStore};
+use tracetimedev_core::state::TimerSettings;
+use tracetimedev_core::{Clock, Result, SystemClock};
+
+/// Variável de ambiente com a senha do arquivo de tokens (sem ela, a chave vem da máquina)
+const PASSPHRASE_ENV: &str = "TRACETIMEDEV_PASSPHRASE";
+
+/// Evento com os perfis cujo token expira em breve
+pub const TOKEN_EVENT: &str = "token://expiring";
+
+/// Com quantos dias de antecedência avisar que o token vai expirar
+const TOKEN_WARNING_DAYS: i64 = 7;
+
+/// Frequência da verificação de validade dos tokens
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Onde os tokens dos perfis ficam guardados
+pub struct Secrets(pub Cached<Box<dyn SecretStore>>);
+
+/// Abre o chaveiro do sistema ou, sem ele, o arquivo cifrado em `data_dir`.
+///
+/// Chamada na inicialização, fora do runtime assíncrono: move os tokens ainda em
+/// texto puro no banco (e apaga os das cópias de migração) e já deixa os de todos
+/// os perfis em memória.
+pub fn open_secrets(data_dir: &Path, conn: &rusqlite::Connection) -> Result<Secrets> {
+    let store: Box<dyn SecretStore> = match Keyring::probe() {
+        Some(keyring) => Box::new(keyring),
+        None => {
+            let source = std::env::var(PASSPHRASE_ENV)
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map_or(KeySource::Machine, KeySource::Passphrase);
+            Box::new(EncryptedFile::open(&data_dir.join("tokens.json"), source)?)
+        }
+    };
+    let secrets = Secrets(Cached::new(store));
+
+    config::seal_tokens(conn, &secrets.0)?;
+    if let Err(e) = config::scrub_backups(data_dir) {
+        eprintln!("Erro ao apagar tokens das cópias do banco: {}", e);
+    }
+    for profile in config::list(conn)? {
+        if let Err(e) = config::token(conn, &secrets.0, profile.id) {
+            eprintln!("Erro ao ler token do perfil {}: {}", profile.name, e);
+        }
+    }
+    Ok(secrets)
+}
+
+/// Roda `f` numa thread fora do runtime assíncrono: o chaveiro não pode ser usado de dentro dele
+pub fn off_runtime<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    std::thread::scope(|scope| scope.spawn(f).join().expect("thread do chaveiro abortou"))
+}
+
+/// Cria ou atualiza o perfil; devolve o id
+#[tauri::command]
+pub fn save_profile(
+    state: tauri::State<AppState>,
+    secrets: tauri::State<Secrets>,
+    sessions: tauri::State<OAuthSessions>,
+    profile: Profile,
+) -> Result<i64> {
+    let conn = state.conn.lock()?;
+    let profile_id = config::save(&conn, &secrets.0, &profile)?;
+    sessions.forget(profile_id);
+    Ok(profile_id)
+}
 
 #[tauri::command]
-pub fn save_config(
+pub fn list_profiles(state: tauri::State<AppState>) -> Result<Vec<Profile>> {
+    let conn = state.conn.lock()?;
+    config::list(&conn)
+}
+
+#[tauri::command]
+pub fn delete_profile(
     state: tauri::State<AppState>,
-    url: String,
-    token: String,
-    labels: String,
-    gitlab_user: String,
-    gitlab_user_id: i64,
-) -> Result<(), String> {
-    let conn = state.conn.lock().unwrap();
-    conn.execute("DELETE FROM config", []).unwrap();
-    conn.execute(
-        "INSERT INTO config (url, token, labels, gitlab_user, gitlab_user_id) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![url, token, labels, gitlab_user, gitlab_user_id],
-    ).map_err(|e| e.to_string())?;
+    secrets: tauri::State<Secrets>,
+    sessions: tauri::State<OAuthSessions>,
+    profile_id: i64,
+) -> Result<()> {
+    let conn = state.conn.lock()?;
+    config::delete(&conn, &secrets.0, profile_id)?;
+    sessions.forget(profile_id);
     Ok(())
 }
 
+/// Cliente HTTP com a configuração de rede salva; se ela não servir mais (ex.: um
+/// certificado foi apagado), o app abre com a padrão e o erro aparece no log
+pub fn open_http_client(conn: &rusqlite::Connection) -> HttpClient {
+    let configured = config::http_settings(conn).and_then(|settings| HttpClient::new(&settings));
+    configured.unwrap_or_else(|e| {
+        eprintln!("Erro na configuração de rede, usando a padrão: {}", e);
+        HttpClient::new(&HttpSettings::default()).expect("Erro ao criar cliente HTTP")
+    })
+}
 
 #[tauri::command]
-pub fn load_config(state: tauri::State<AppState>) -> Result<(String, String, String, String, i64), String> {
-    let conn = state.conn.lock().unwrap();
-    let mut stmt = conn.prepare("SELECT url, token, labels, gitlab_user, gitlab_user_id FROM config LIMIT 1").unwrap();
-    let mut rows = stmt.query([]).unwrap();
-    if let Some(row) = rows.next().unwrap() {
-        let url: String = row.get(0).unwrap();
-        let token: String = row.get(1).unwrap();
-        let labels: String = row.get(2).unwrap_or_default();
-        let gitlab_user: String = row.get(3).unwrap_or_default();
-        let gitlab_user_id: i64 = row.get(4).unwrap_or(0);
-        Ok((url, token, labels, gitlab_user, gitlab_user_id))
-    } else {
-        Err("Nenhuma configuração encontrada".into())
-    }
+pub fn load_http_settings(state: tauri::State<AppState>) -> Result<HttpSettings> {
+    let conn = state.conn.lock()?;
+    config::http_settings(&conn)
+}
+
+/// Valida (montando o cliente), salva e passa a usar a nova configuração de rede
+#[tauri::command]
+pub fn save_http_settings(
+    state: tauri::State<AppState>,
+    http_client: tauri::State<HttpClient>,
+    sessions: tauri::State<OAuthSessions>,
+    settings: HttpSettings,
+) -> Result<()> {
+    let client = http::client(&settings)?;
+    let conn = state.conn.lock()?;
+    config::save_http_settings(&conn, &settings)?;
+    http_client.replace(client)?;
+    // as sessões abertas guardam o cliente antigo
+    sessions.clear();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn load_timer_settings(state: tauri::State<AppState>) -> Result<TimerSettings> {
+    let conn = state.conn.lock()?;
+    config::timer_settings(&conn)
+}
+
+#[tauri::command]
+pub fn save_timer_settings(state: tauri::State<AppState>, settings: TimerSettings) -> Result<()> {
+    let conn = state.conn.lock()?;
+    config::save_timer_settings(&conn, &settings)
+}
+
+/// Perfis com token expirando nos próximos dias
+#[tauri::command]
+pub fn expiring_tokens(app: AppHandle) -> Result<Vec<TokenExpiry>> {
+    expiring(&app)
+}
+
+/// Inicia a thread que avisa as janelas quando algum token está para expirar
+pub fn start_expiry_watch(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        match expiring(&app) {
+            Ok(expiring) if !expiring.is_empty() => {
+                let _ = app.emit(TOKEN_EVENT, expiring);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Erro ao verificar validade dos tokens: {}", e),
+        }
+        std::thread::sleep(EXPIRY_CHECK_INTERVAL);
+    });
+}
+
+fn expiring(app: &AppHandle) -> Result<Vec<TokenExpiry>> {
+    let state = app.state::<AppState>();
+    let conn = state.conn.lock()?;
+    config::expiring_tokens(&conn, SystemClock.now(), TOKEN_WARNING_DAYS)
 }
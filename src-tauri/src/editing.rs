@@ -0,0 +1,98 @@
+use crate::AppState;
+use crate::sync::{self, SyncQueue};
+use crate::ticker;
+use tauri::{AppHandle, State};
+use tracetimedev_core::editing::{Interval, Span};
+use tracetimedev_core::models::IssueRef;
+use tracetimedev_core::{Result, Tracker};
+
+/// Lança uma sessão finalizada com intervalos informados à mão
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub fn create_manual_session(
+    app: AppHandle,
+    state: State<AppState>,
+    queue: State<SyncQueue>,
+    profile_id: i64,
+    group_id: i64,
+    project_id: i64,
+    issue: IssueRef,
+    label: String,
+    intervals: Vec<Span>,
+) -> Result<i64> {
+    let conn = state.conn.lock()?;
+    let session_id = Tracker::new(&conn).create_manual(profile_id, group_id, project_id, &issue, &label, &intervals)?;
+    sync::emit_status(&app, &conn);
+    ticker::refresh(&app, &conn);
+    queue.wake();
+    Ok(session_id)
+}
+
+/// Intervalos da sessão em ordem cronológica
+#[tauri::command]
+pub fn session_intervals(state: State<AppState>, session_id: i64) -> Result<Vec<Interval>> {
+    let conn = state.conn.lock()?;
+    Tracker::new(&conn).intervals(session_id)
+}
+
+/// Corrige início e fim de um intervalo
+#[tauri::command]
+pub fn update_interval(
+    app: AppHandle,
+    state: State<AppState>,
+    queue: State<SyncQueue>,
+    interval_id: i64,
+    interval: Span,
+) -> Result<()> {
+    let conn = state.conn.lock()?;
+    Tracker::new(&conn).update_interval(interval_id, &interval)?;
+    sync::emit_status(&app, &conn);
+    ticker::refresh(&app, &conn);
+    queue.wake();
+    Ok(())
+}
+
+/// Remove um intervalo da sessão
+#[tauri::command]
+pub fn delete_interval(app: AppHandle, state: State<AppState>, queue: State<SyncQueue>, interval_id: i64) -> Result<()> {
+    let conn = state.conn.lock()?;
+    Tracker::new(&conn).delete_interval(interval_id)?;
+    sync::emit_status(&app, &conn);
+    ticker::refresh(&app, &conn);
+    queue.wake();
+    Ok(())
+}
+
+/// Divide a sessão no instante `at`; retorna a sessão nova
+#[tauri::command]
+pub fn split_session(
+    app: AppHandle,
+    state: State<AppState>,
+    queue: State<SyncQueue>,
+    session_id: i64,
+    at: i64,
+) -> Result<i64> {
+    let conn = state.conn.lock()?;
+    let new_id = Tracker::new(&conn).split_session(session_id, at)?;
+    sync::emit_status(&app, &conn);
+    ticker::refresh(&app, &conn);
+    queue.wake();
+    Ok(new_id)
+}
+
+/// Junta `source_id` em `target_id` (mesma issue)
+#[tauri::command]
+pub fn merge_sessions(
+    app: AppHandle,
+    state: State<AppState>,
+    queue: State<SyncQueue>,
+    target_id: i64,
+    source_id: i64,
+) -> Result<()> {
+    let conn = state.conn.lock()?;
+    Tracker::new(&conn).merge_sessions(target_id, source_id)?;
+    sync::emit_status(&app, &conn);
+    ticker::refresh(&app, &conn);
+    queue.wake();
+    Ok(())
+}
@@ -1,106 +1,357 @@
 
+use crate::config::{off_runtime, Secrets};
 use crate::AppState;
-use reqwest;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_opener::OpenerExt;
+use tracetimedev_core::backend::{Fetched, TrackerBackend};
+use tracetimedev_core::cache::{self, ListKind};
+use tracetimedev_core::models::{Group, Issue, IssueFilter, Note, Project, TimeStats, TokenInfo, User};
+use tracetimedev_core::config::{self, AuthKind, Profile};
+use tracetimedev_core::gitlab::{missing_scopes, GitLabClient};
+use tracetimedev_core::http::{self, HttpSettings};
+use tracetimedev_core::oauth::{self, Loopback, OAuthSession, OAuthTokens};
+use tracetimedev_core::{Backend, BackendKind, Error, Result};
 
-#[tauri::command]
-pub fn test_gitlab(url: String, token: String) -> Result<(String, i64), String> {
-    let client = reqwest::blocking::Client::new();
-    let resp = client
-        .get(format!("{}/api/v4/user", url))
-        .bearer_auth(token)
-        .send()
-        .map_err(|e| e.to_string())?;
-
-    if !resp.status().is_success() {
-        return Err(format!("Erro: {}", resp.status()));
+/// Evento emitido quando uma listagem em cache foi atualizada em segundo plano
+pub const CACHE_EVENT: &str = "gitlab://refreshed";
+
+/// Cliente HTTP único, compartilhado por todas as chamadas ao rastreador;
+/// trocado inteiro quando a configuração de rede muda
+pub struct HttpClient(RwLock<reqwest::Client>);
+
+impl HttpClient {
+    pub fn new(settings: &HttpSettings) -> Result<Self> {
+        Ok(HttpClient(RwLock::new(http::client(settings)?)))
     }
 
-    let json: serde_json::Value = resp.json().map_err(|e| e.to_string())?;
-    let username = json["username"].as_str().unwrap_or("").to_string();
-    let id = json["id"].as_i64().unwrap_or(0);
+    pub fn get(&self) -> Result<reqwest::Client> {
+        Ok(self.0.read()?.clone())
+    }
 
-    Ok((username, id))
+    pub fn replace(&self, client: reqwest::Client) -> Result<()> {
+        *self.0.write()? = client;
+        Ok(())
+    }
 }
 
-#[tauri::command]
-pub async fn gitlab_groups(state: tauri::State<'_, AppState>) -> Result<Vec<serde_json::Value>, String> {
-    let (url, token) = {
-        let conn = state.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT url, token FROM config LIMIT 1").unwrap();
-        let mut rows = stmt.query([]).unwrap();
-
-        if let Some(row) = rows.next().unwrap() {
-            let url: String = row.get::<_, String>(0).unwrap();
-            let token: String = row.get::<_, String>(1).unwrap();
-            (url, token)
-        } else {
-            return Err("Configuração não encontrada".into());
+/// Sessões OAuth abertas, uma por perfil. O GitLab troca o refresh token a cada
+/// renovação: comandos, cache e envio precisam usar a mesma para não renovar em dobro.
+#[derive(Default)]
+pub struct OAuthSessions(Mutex<HashMap<i64, Arc<OAuthSession>>>);
+
+impl OAuthSessions {
+    /// Descarta a sessão do perfil (novo login, perfil alterado ou apagado)
+    pub fn forget(&self, profile_id: i64) {
+        if let Ok(mut sessions) = self.0.lock() {
+            sessions.remove(&profile_id);
         }
-    };
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(format!("{}/api/v4/groups", url))
-        .bearer_auth(token)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    }
+
+    /// Descarta todas (o cliente HTTP foi trocado)
+    pub fn clear(&self) {
+        if let Ok(mut sessions) = self.0.lock() {
+            sessions.clear();
+        }
+    }
+}
+
+/// Backend da instância do perfil, com o token guardado
+pub fn profile_backend(app: &AppHandle, profile: &Profile) -> Result<Backend> {
+    let http = &app.state::<HttpClient>().get()?;
+    let secret = stored_token(app, profile.id)?;
+    match profile.auth_kind {
+        AuthKind::Token => Ok(Backend::new(profile.backend_kind, http, &profile.url, &secret)),
+        AuthKind::OAuth if profile.backend_kind != BackendKind::GitLab => {
+            Err(Error::InvalidState("Login OAuth disponível só para o GitLab".into()))
+        }
+        AuthKind::OAuth => {
+            let session = oauth_session(app, http, profile, &secret)?;
+            Ok(Backend::GitLab(GitLabClient::with_oauth(http, session)))
+        }
+    }
+}
+
+/// Sessão OAuth já aberta do perfil ou, se não houver (ou a URL mudou), uma nova com os tokens guardados
+fn oauth_session(app: &AppHandle, http: &reqwest::Client, profile: &Profile, secret: &str) -> Result<Arc<OAuthSession>> {
+    let sessions = app.state::<OAuthSessions>();
+    let mut sessions = sessions.0.lock()?;
+    let open = sessions.get(&profile.id).filter(|session| {
+        session.base_url() == profile.url.trim_end_matches('/') && session.client_id() == profile.oauth_client_id
+    });
+    if let Some(session) = open {
+        return Ok(session.clone());
+    }
+
+    let tokens = OAuthTokens::from_secret(secret)?;
+    let (app, profile_id) = (app.clone(), profile.id);
+    let persist = Box::new(move |tokens: &OAuthTokens| {
+        let (app, tokens) = (app.clone(), tokens.clone());
+        off_runtime(move || config::save_oauth_tokens(&app.state::<Secrets>().0, profile_id, &tokens))
+    });
+    let session = Arc::new(OAuthSession::new(http, &profile.url, &profile.oauth_client_id, tokens, persist));
+    sessions.insert(profile.id, session.clone());
+    Ok(session)
+}
 
-    let json: Vec<serde_json::Value> = resp.json().await.map_err(|e| e.to_string())?;
-    Ok(json)
+/// Backend do perfil salvo
+pub fn backend(app: &AppHandle, profile_id: i64) -> Result<Backend> {
+    profile_backend(app, &load_profile(app, profile_id)?)
 }
 
+fn load_profile(app: &AppHandle, profile_id: i64) -> Result<Profile> {
+    let state = app.state::<AppState>();
+    let conn = state.conn.lock()?;
+    config::load(&conn, profile_id)
+}
 
+fn stored_token(app: &AppHandle, profile_id: i64) -> Result<String> {
+    let state = app.state::<AppState>();
+    let conn = state.conn.lock()?;
+    config::token(&conn, &app.state::<Secrets>().0, profile_id)
+}
+
+/// Resultado do teste de conexão: usuário, dados do token e escopos que faltam
+#[derive(Serialize)]
+pub struct ConnectionCheck {
+    #[serde(flatten)]
+    user: User,
+    token: Option<TokenInfo>,
+    missing_scopes: Vec<String>,
+}
+
+/// Testa a conexão; com `token` vazio valida a credencial guardada em `profile_id`
+/// (no OAuth, renovando a sessão se preciso)
 #[tauri::command]
-pub async fn gitlab_projects(state: tauri::State<'_, AppState>, group_id: i64) -> Result<Vec<serde_json::Value>, String> {
-    let (url, token) = {
-        let conn = state.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT url, token FROM config LIMIT 1").unwrap();
-        let mut rows = stmt.query([]).unwrap();
-
-        if let Some(row) = rows.next().unwrap() {
-            let url: String = row.get::<_, String>(0).unwrap();
-            let token: String = row.get::<_, String>(1).unwrap();
-            (url, token)
-        } else {
-            return Err("Configuração não encontrada".into());
+pub async fn test_gitlab(
+    app: AppHandle,
+    url: String,
+    token: String,
+    backend_kind: Option<BackendKind>,
+    profile_id: Option<i64>,
+) -> Result<ConnectionCheck> {
+    let backend_kind = backend_kind.unwrap_or_default();
+    let backend = match profile_id {
+        Some(id) if token.trim().is_empty() => {
+            let profile = Profile { url, backend_kind, ..load_profile(&app, id)? };
+            profile_backend(&app, &profile)?
         }
+        _ => Backend::new(backend_kind, &app.state::<HttpClient>().get()?, &url, &token),
     };
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(format!("{}/api/v4/groups/{}/projects", url, group_id))
-        .bearer_auth(token)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let user = backend.current_user().await?;
+    // os dados do token são um extra: a conexão em si já foi validada
+    let token = backend.token_info().await.unwrap_or_else(|e| {
+        eprintln!("Erro ao consultar o token: {}", e);
+        None
+    });
+    let missing_scopes = token.as_ref().map(missing_scopes).unwrap_or_default();
+    Ok(ConnectionCheck { user, token, missing_scopes })
+}
 
-    let json: Vec<serde_json::Value> = resp.json().await.map_err(|e| e.to_string())?;
-    Ok(json)
+/// Login OAuth (PKCE) no navegador com retorno para uma porta local; guarda os
+/// tokens no perfil, criando-o se for novo, e devolve o id
+#[tauri::command]
+pub async fn gitlab_oauth_login(app: AppHandle, profile: Profile) -> Result<i64> {
+    let client_id = profile.oauth_client_id.trim().to_string();
+    if client_id.is_empty() {
+        return Err(Error::InvalidState("Informe o Application ID cadastrado no GitLab".into()));
+    }
+    let http = app.state::<HttpClient>().get()?;
+
+    let loopback = Loopback::bind().await?;
+    let request = oauth::authorization(&profile.url, &client_id, &loopback.redirect_uri())?;
+    app.opener()
+        .open_url(request.url.as_str(), None::<&str>)
+        .map_err(|e| Error::Internal(format!("não foi possível abrir o navegador: {}", e)))?;
+    let code = loopback.wait_code(&request.state).await?;
+    let tokens = oauth::exchange_code(&http, &profile.url, &client_id, &code, &request).await?;
+    let user = GitLabClient::new(&http, &profile.url, &tokens.access_token).current_user().await?;
+
+    let profile = Profile {
+        backend_kind: BackendKind::GitLab,
+        auth_kind: AuthKind::OAuth,
+        oauth_client_id: client_id,
+        token: tokens.to_secret()?,
+        gitlab_user: user.username,
+        gitlab_user_id: user.id,
+        ..profile
+    };
+    let saved = app.clone();
+    let profile_id = off_runtime(move || {
+        let state = saved.state::<AppState>();
+        let conn = state.conn.lock()?;
+        config::save(&conn, &saved.state::<Secrets>().0, &profile)
+    })?;
+    // a sessão antiga do perfil tem o refresh token que acabou de ser substituído
+    app.state::<OAuthSessions>().forget(profile_id);
+    Ok(profile_id)
+}
+
+/// Grupo com o perfil de onde veio (ids de instâncias diferentes podem coincidir)
+#[derive(Serialize)]
+pub struct ProfileGroup {
+    profile_id: i64,
+    profile_name: String,
+    #[serde(flatten)]
+    group: Group,
 }
 
+/// Grupos de todos os perfis; um perfil inacessível não esconde os outros
 #[tauri::command]
-pub async fn gitlab_issues(state: tauri::State<'_, AppState>, project_id: i64) -> Result<Vec<serde_json::Value>, String> {
-    let (url, token) = {
-        let conn = state.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT url, token FROM config LIMIT 1").unwrap();
-        let mut rows = stmt.query([]).unwrap();
-
-        if let Some(row) = rows.next().unwrap() {
-            let url: String = row.get::<_, String>(0).unwrap();
-            let token: String = row.get::<_, String>(1).unwrap();
-            (url, token)
-        } else {
-            return Err("Configuração não encontrada".into());
+pub async fn gitlab_groups(app: AppHandle) -> Result<Vec<ProfileGroup>> {
+    let profiles = {
+        let state = app.state::<AppState>();
+        let conn = state.conn.lock()?;
+        config::list(&conn)?
+    };
+
+    let mut groups = Vec::new();
+    let mut last_error = None;
+    for profile in profiles {
+        let fetched = cached_list(
+            app.clone(),
+            profile.id,
+            ListKind::Groups,
+            None,
+            None,
+            true,
+            |backend, limit, etag| async move { backend.containers(limit, etag.as_deref()).await },
+        )
+        .await;
+        match fetched {
+            Ok(list) => groups.extend(list.into_iter().map(|group| ProfileGroup {
+                profile_id: profile.id,
+                profile_name: profile.name.clone(),
+                group,
+            })),
+            Err(e) => {
+                eprintln!("Erro ao carregar grupos do perfil {}: {}", profile.name, e);
+                last_error = Some(e);
+            }
         }
+    }
+    match last_error {
+        Some(e) if groups.is_empty() => Err(e),
+        _ => Ok(groups),
+    }
+}
+
+#[tauri::command]
+pub async fn gitlab_projects(app: AppHandle, profile_id: i64, group_id: i64) -> Result<Vec<Project>> {
+    cached_list(
+        app,
+        profile_id,
+        ListKind::Projects,
+        Some(group_id),
+        None,
+        true,
+        move |backend, limit, etag| async move { backend.projects(group_id, limit, etag.as_deref()).await },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn gitlab_issues(
+    app: AppHandle,
+    profile_id: i64,
+    project_id: i64,
+    filter: Option<IssueFilter>,
+) -> Result<Vec<Issue>> {
+    let user_id = {
+        let state = app.state::<AppState>();
+        let conn = state.conn.lock()?;
+        config::load(&conn, profile_id)?.gitlab_user_id
     };
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(format!("{}/api/v4/projects/{}/issues", url, project_id))
-        .bearer_auth(token)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let filter = filter.unwrap_or_else(IssueFilter::assigned_to_me);
 
-    let json: Vec<serde_json::Value> = resp.json().await.map_err(|e| e.to_string())?;
-    Ok(json)
+    // buscas por texto são pontuais, não vale guardar
+    let use_cache = filter.search.as_deref().is_none_or(|s| s.trim().is_empty());
+    let key_filter = filter.clone();
+    cached_list(
+        app,
+        profile_id,
+        ListKind::Issues,
+        Some(project_id),
+        Some(key_filter),
+        use_cache,
+        move |backend, limit, etag| async move {
+            backend.issues(project_id, &filter, user_id, limit, etag.as_deref()).await
+        },
+    )
+    .await
 }
 
+/// Tempo estimado e gasto na issue
+#[tauri::command]
+pub async fn gitlab_time_stats(app: AppHandle, profile_id: i64, project_id: i64, issue_iid: i64) -> Result<TimeStats> {
+    backend(&app, profile_id)?.time_stats(project_id, issue_iid).await
+}
+
+/// Comentários da issue
+#[tauri::command]
+pub async fn gitlab_issue_notes(app: AppHandle, profile_id: i64, project_id: i64, issue_iid: i64) -> Result<Vec<Note>> {
+    backend(&app, profile_id)?.issue_notes(project_id, issue_iid).await
+}
+
+#[derive(Serialize, Clone)]
+struct CacheRefreshed {
+    profile_id: i64,
+    kind: ListKind,
+    parent_id: Option<i64>,
+}
+
+/// Devolve a listagem do cache na hora e, se estiver velha, atualiza em segundo plano.
+///
+/// O filtro das issues entra na chave do cache. Sem cache (ou com `use_cache` falso)
+/// a busca é feita direto no rastreador.
+async fn cached_list<T, F, Fut>(
+    app: AppHandle,
+    profile_id: i64,
+    kind: ListKind,
+    parent_id: Option<i64>,
+    filter: Option<IssueFilter>,
+    use_cache: bool,
+    fetch: F,
+) -> Result<Vec<T>>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+    F: FnOnce(Backend, usize, Option<String>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Fetched<T>>> + Send,
+{
+    let backend = backend(&app, profile_id)?;
+    let key = cache::key(profile_id, kind, parent_id, filter.as_ref());
+    let state = app.state::<AppState>();
+    let cached = if use_cache {
+        let conn = state.conn.lock()?;
+        cache::get::<T>(&conn, &key)?
+    } else {
+        None
+    };
+
+    let Some(entry) = cached else {
+        return cache::fetch_list(&state.conn, profile_id, &key, kind, parent_id, use_cache, |limit| {
+            fetch(backend, limit, None)
+        })
+        .await;
+    };
+
+    if entry.is_stale() {
+        let etag = entry.etag.clone();
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let state = app.state::<AppState>();
+            match cache::refresh_list(&state.conn, profile_id, &key, kind, parent_id, |limit| fetch(backend, limit, etag)).await {
+                Ok(true) => {
+                    let _ = app.emit(CACHE_EVENT, CacheRefreshed { profile_id, kind, parent_id });
+                }
+                Ok(false) => {}
+                Err(e) => eprintln!("Erro ao atualizar cache de {}: {}", kind.as_str(), e),
+            }
+        });
+    }
+    Ok(entry.items)
+}
@@ -0,0 +1,11 @@
+use crate::AppState;
+use tauri::State;
+use tracetimedev_core::history::{HistoryFilter, HistoryPage};
+use tracetimedev_core::{Result, Tracker};
+
+/// Sessões anteriores filtradas e paginadas, para a janela de histórico
+#[tauri::command]
+pub fn session_history(state: State<AppState>, filter: HistoryFilter) -> Result<HistoryPage> {
+    let conn = state.conn.lock()?;
+    Tracker::new(&conn).history(&filter)
+}
@@ -0,0 +1,87 @@
+use crate::sync;
+use crate::ticker;
+use crate::AppState;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tracetimedev_core::config;
+use tracetimedev_core::idle::{self, IdleBlock, IdleDecision, IdleEvent, IdleSource};
+use tracetimedev_core::{Result, Tracker};
+
+/// Evento com o bloco ausente, ao passar do limite e quando o usuário volta
+pub const IDLE_EVENT: &str = "idle://detected";
+
+/// Frequência da leitura do tempo ocioso
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Inicia a thread que separa o tempo ausente do timer rodando
+pub fn start_idle_watch(app: AppHandle) {
+    std::thread::spawn(move || {
+        let source = idle::system_source();
+        loop {
+            std::thread::sleep(IDLE_CHECK_INTERVAL);
+            match check(&app, source.as_ref()) {
+                Ok(Some(event)) => notify(&app, event),
+                Ok(None) => {}
+                Err(e) => eprintln!("Erro ao verificar tempo ocioso: {}", e),
+            }
+        }
+    });
+}
+
+fn check(app: &AppHandle, source: &dyn IdleSource) -> Result<Option<IdleEvent>> {
+    let state = app.state::<AppState>();
+    let threshold = config::timer_settings(&*state.conn.lock()?)?.idle_minutes * 60;
+    if threshold <= 0 {
+        return Ok(None);
+    }
+    // a leitura passa pelo D-Bus e pode demorar: não segura o banco dos comandos
+    let Some(idle) = source.idle_seconds()? else {
+        return Ok(None);
+    };
+    let conn = state.conn.lock()?;
+    let event = Tracker::new(&conn).check_idle(idle, threshold)?;
+    if event.is_some() {
+        ticker::refresh(app, &conn);
+    }
+    Ok(event)
+}
+
+/// Avisa as janelas; na volta do usuário abre o popup para a decisão
+fn notify(app: &AppHandle, event: IdleEvent) {
+    let (block, back) = match event {
+        IdleEvent::Started(block) => (block, false),
+        IdleEvent::Ended(block) => (block, true),
+    };
+    let _ = app.emit(IDLE_EVENT, block);
+    if back {
+        if let Some(window) = app.get_webview_window("Time") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }
+}
+
+/// Tempo ausente aguardando decisão do usuário
+#[tauri::command]
+pub fn get_idle(state: State<AppState>) -> Result<Option<IdleBlock>> {
+    let conn = state.conn.lock()?;
+    Tracker::new(&conn).pending_idle()
+}
+
+/// Mantém, descarta ou passa o tempo ausente para outra issue
+#[tauri::command]
+pub fn resolve_idle(
+    app: AppHandle,
+    state: State<AppState>,
+    queue: State<sync::SyncQueue>,
+    interval_id: i64,
+    decision: IdleDecision,
+) -> Result<Option<i64>> {
+    let conn = state.conn.lock()?;
+    let reassigned = Tracker::new(&conn).resolve_idle(interval_id, &decision)?;
+    ticker::refresh(&app, &conn);
+
+    sync::emit_status(&app, &conn);
+    queue.wake();
+    Ok(reassigned)
+}
@@ -4,11 +4,19 @@ mod tray;
 mod timer;
 mod config;
 mod gitlab;
-use tauri::Manager;
-use std::sync::{Arc, Mutex};
-use timer::{TimerState, SharedTimer};
+mod sync;
+mod recovery;
+mod cache;
+mod editing;
+mod history;
+mod ticker;
+mod idle;
+mod power;
+use tauri::{Manager, RunEvent};
+use std::sync::Mutex;
 use rusqlite::Connection; 
 use std::fs;
+use tracetimedev_core::{migrations, Tracker};
 
 struct AppState { 
     conn: Mutex<Connection>
@@ -16,74 +24,86 @@ struct AppState {
 
 fn main() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
             if let Some(window) = app.get_webview_window("Time") {
                 let _ = window.show();
                 let _ = window.set_focus();
             }
         }))
-        .manage::<SharedTimer>(Arc::new(Mutex::new(TimerState::default())))
         .invoke_handler(tauri::generate_handler![
             timer::start_timer,
+            timer::switch_task,
             timer::pause_timer,
             timer::stop_timer,
             timer::resume_timer,
-            config::save_config,
-            config::load_config,
+            config::save_profile,
+            config::list_profiles,
+            config::delete_profile,
+            config::expiring_tokens,
+            config::load_http_settings,
+            config::save_http_settings,
+            config::load_timer_settings,
+            config::save_timer_settings,
             gitlab::test_gitlab,
+            gitlab::gitlab_oauth_login,
             gitlab::gitlab_groups,
             gitlab::gitlab_projects,
             gitlab::gitlab_issues,
+            gitlab::gitlab_time_stats,
+            gitlab::gitlab_issue_notes,
             timer::get_session_time,
             timer::resume_last_session,
+            editing::create_manual_session,
+            editing::session_intervals,
+            editing::update_interval,
+            editing::delete_interval,
+            editing::split_session,
+            editing::merge_sessions,
+            history::session_history,
+            sync::sync_status,
+            sync::sync_retry,
+            recovery::get_recovery,
+            recovery::resolve_recovery,
+            idle::get_idle,
+            idle::resolve_idle,
+            cache::gitlab_cache_purge,
         ])
         .setup(|app| {
             let app_data_dir_path = app.path().app_data_dir().expect("Failed to get app data dir");
             fs::create_dir_all(&app_data_dir_path).expect("Não conseguiu criar pasta de dados");
             let db_path = app_data_dir_path.join("config.db");
-            let conn = Connection::open(db_path).expect("Erro ao abrir banco");
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS config (
-                        id INTEGER PRIMARY KEY,
-                        url TEXT NOT NULL,
-                        token TEXT NOT NULL,
-                        labels TEXT DEFAULT '',
-                        gitlab_user TEXT,
-                        gitlab_user_id INTEGER
-                    )",
-                [],
-            ).unwrap();
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS sessions (
-                        id INTEGER PRIMARY KEY AUTOINCREMENT,
-                        group_id INTEGER,
-                        project_id INTEGER,
-                        issue_id INTEGER,
-                        label TEXT,
-                        status TEXT,
-                        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                        updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-                    )",
-                [],
-            ).unwrap();
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS session_intervals (
-                        id INTEGER PRIMARY KEY AUTOINCREMENT,
-                        session_id INTEGER,
-                        start_time DATETIME,
-                        end_time DATETIME,
-                        FOREIGN KEY(session_id) REFERENCES sessions(id)
-                    )",
-                [],
-            ).unwrap();
+            let mut conn = Connection::open(&db_path).expect("Erro ao abrir banco");
+            let backup_path = app_data_dir_path.join(format!(
+                "config.db.v{}.bak",
+                migrations::current_version(&conn).expect("Erro ao ler versão do banco")
+            ));
+            migrations::migrate(&mut conn, Some(&backup_path)).expect("Erro ao migrar banco");
+            Tracker::new(&conn).close_orphaned_intervals().expect("Erro ao recuperar intervalos abertos");
+            let secrets = config::open_secrets(&app_data_dir_path, &conn).expect("Erro ao abrir tokens");
+            let snapshot = Tracker::new(&conn).snapshot().expect("Erro ao ler sessão atual");
 
+            app.manage(secrets);
+            app.manage(config::open_http_client(&conn));
+            app.manage(gitlab::OAuthSessions::default());
             app.manage(AppState {
                 conn: Mutex::new(conn),
             });
+            app.manage(sync::start_worker(app.handle().clone()));
+            recovery::start_heartbeat(app.handle().clone());
+            config::start_expiry_watch(app.handle().clone());
+            idle::start_idle_watch(app.handle().clone());
+            power::start_power_watch(app.handle().clone());
 
             tray::setup_tray(app.handle())?;
+            app.manage(ticker::start_ticker(app.handle().clone(), snapshot));
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                power::on_exit(app);
+            }
+        });
 }
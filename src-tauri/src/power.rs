@@ -0,0 +1,35 @@
+use crate::ticker;
+use crate::AppState;
+use tauri::{AppHandle, Manager};
+use tracetimedev_core::config;
+use tracetimedev_core::power::{self, PowerEvent};
+use tracetimedev_core::{Result, Tracker};
+
+/// Inicia a thread que pausa o timer ao bloquear a tela, suspender ou desligar
+pub fn start_power_watch(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut source = power::system_source();
+        while let Some(event) = source.next_event() {
+            if let Err(e) = handle(&app, event) {
+                eprintln!("Erro ao tratar evento do sistema: {}", e);
+            }
+        }
+    });
+}
+
+/// Pausa a sessão rodando ao sair do app, pelo mesmo caminho do desligamento
+pub fn on_exit(app: &AppHandle) {
+    if let Err(e) = handle(app, PowerEvent::Shutdown) {
+        eprintln!("Erro ao pausar o timer na saída: {}", e);
+    }
+}
+
+fn handle(app: &AppHandle, event: PowerEvent) -> Result<()> {
+    let state = app.state::<AppState>();
+    let conn = state.conn.lock()?;
+    let auto_resume = config::timer_settings(&conn)?.auto_resume;
+    if Tracker::new(&conn).handle_power(event, auto_resume)?.is_some() {
+        ticker::refresh(app, &conn);
+    }
+    Ok(())
+}
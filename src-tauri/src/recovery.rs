@@ -0,0 +1,42 @@
+
+use crate::AppState;
+use crate::ticker;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tracetimedev_core::recovery::Recovery;
+use tracetimedev_core::{Result, Tracker};
+
+/// Frequência com que o intervalo aberto registra que o app ainda está vivo
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Inicia a thread que atualiza o heartbeat do intervalo aberto
+pub fn start_heartbeat(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(HEARTBEAT_INTERVAL);
+        if let Err(e) = heartbeat(&app) {
+            eprintln!("Erro ao gravar heartbeat: {}", e);
+        }
+    });
+}
+
+fn heartbeat(app: &AppHandle) -> Result<()> {
+    let state = app.state::<AppState>();
+    let conn = state.conn.lock()?;
+    Tracker::new(&conn).heartbeat()
+}
+
+/// Tempo recuperado aguardando decisão do usuário
+#[tauri::command]
+pub fn get_recovery(state: State<AppState>) -> Result<Option<Recovery>> {
+    let conn = state.conn.lock()?;
+    Tracker::new(&conn).pending_recovery()
+}
+
+/// Mantém ou descarta o tempo recuperado da sessão
+#[tauri::command]
+pub fn resolve_recovery(app: AppHandle, state: State<AppState>, session_id: i64, keep: bool) -> Result<()> {
+    let conn = state.conn.lock()?;
+    Tracker::new(&conn).resolve_recovery(session_id, keep)?;
+    ticker::refresh(&app, &conn);
+    Ok(())
+}
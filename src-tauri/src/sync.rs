@@ -0,0 +1,126 @@
+use crate::AppState;
+use crate::gitlab;
+use rusqlite::Connection;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+use tauri::async_runtime::block_on;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tracetimedev_core::config::{self, Profile};
+use tracetimedev_core::sync::{self as outbox, OutboxStatus};
+use tracetimedev_core::Result;
+
+/// Evento emitido sempre que a fila de envio muda
+pub const OUTBOX_EVENT: &str = "sync://outbox";
+
+/// Intervalo máximo entre duas verificações da fila
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Canal para acordar o worker de envio
+pub struct SyncQueue {
+    wake: Sender<()>,
+}
+
+impl SyncQueue {
+    pub fn wake(&self) {
+        let _ = self.wake.send(());
+    }
+}
+
+/// Inicia a thread que envia os lançamentos pendentes
+pub fn start_worker(app: AppHandle) -> SyncQueue {
+    let (wake, rx) = mpsc::channel();
+    std::thread::spawn(move || worker_loop(app, rx));
+    let queue = SyncQueue { wake };
+    queue.wake(); // envia o que ficou pendente da última execução
+    queue
+}
+
+fn worker_loop(app: AppHandle, rx: Receiver<()>) {
+    // só este worker envia: o que ficou marcado em envio é de uma execução anterior
+    if let Err(e) = release_sending(&app) {
+        eprintln!("Erro ao liberar lançamentos em envio: {}", e);
+    }
+    if let Err(e) = backfill_issue_refs(&app) {
+        eprintln!("Erro ao preencher issues das sessões: {}", e);
+    }
+    loop {
+        if let Err(e) = process_due(&app) {
+            eprintln!("Erro ao processar fila de envio: {}", e);
+        }
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+/// Envia os lançamentos vencidos de cada perfil e avisa as janelas se algo mudou
+fn process_due(app: &AppHandle) -> Result<()> {
+    let state = app.state::<AppState>();
+    let mut attempted = 0;
+    for profile in profiles(app)? {
+        let backend = match gitlab::profile_backend(app, &profile) {
+            Ok(backend) => backend,
+            Err(e) => {
+                eprintln!("Erro ao enviar lançamentos do perfil {}: {}", profile.name, e);
+                continue;
+            }
+        };
+        match block_on(outbox::send_due(&state.conn, profile.id, &backend)) {
+            Ok(count) => attempted += count,
+            Err(e) => eprintln!("Erro ao enviar lançamentos do perfil {}: {}", profile.name, e),
+        }
+    }
+    if attempted > 0 {
+        let conn = state.conn.lock()?;
+        emit_status(app, &conn);
+    }
+    Ok(())
+}
+
+fn release_sending(app: &AppHandle) -> Result<()> {
+    let state = app.state::<AppState>();
+    let conn = state.conn.lock()?;
+    outbox::release_sending(&conn)
+}
+
+fn backfill_issue_refs(app: &AppHandle) -> Result<()> {
+    let state = app.state::<AppState>();
+    for profile in profiles(app)? {
+        let backend = gitlab::profile_backend(app, &profile)?;
+        block_on(outbox::backfill_issue_refs(&state.conn, profile.id, &backend))?;
+    }
+    Ok(())
+}
+
+/// Perfis salvos; sem nenhum, os lançamentos ficam na fila
+fn profiles(app: &AppHandle) -> Result<Vec<Profile>> {
+    let state = app.state::<AppState>();
+    let conn = state.conn.lock()?;
+    config::list(&conn)
+}
+
+/// Avisa as janelas sobre a quantidade de lançamentos pendentes
+pub fn emit_status(app: &AppHandle, conn: &Connection) {
+    if let Ok(status) = outbox::outbox_status(conn) {
+        let _ = app.emit(OUTBOX_EVENT, status);
+    }
+}
+
+/// Quantidade de lançamentos aguardando envio
+#[tauri::command]
+pub fn sync_status(state: State<AppState>) -> Result<OutboxStatus> {
+    let conn = state.conn.lock()?;
+    outbox::outbox_status(&conn)
+}
+
+/// Força nova tentativa imediata de todos os lançamentos pendentes
+#[tauri::command]
+pub fn sync_retry(state: State<AppState>, queue: State<SyncQueue>) -> Result<()> {
+    {
+        let conn = state.conn.lock()?;
+        outbox::retry_all(&conn)?;
+    }
+    queue.wake();
+    Ok(())
+}
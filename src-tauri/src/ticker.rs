@@ -0,0 +1,72 @@
+use crate::tray::{update_tray_icon, update_tray_tooltip};
+use rusqlite::Connection;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracetimedev_core::ticker::TimerSnapshot;
+use tracetimedev_core::{Clock, SystemClock, Tracker};
+
+/// Evento com o tempo corrido da sessão atual, emitido a cada segundo enquanto o timer roda
+pub const TICK_EVENT: &str = "timer://tick";
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sessão atual compartilhada com a thread que emite os ticks
+pub struct Ticker {
+    snapshot: Arc<Mutex<TimerSnapshot>>,
+    wake: Sender<()>,
+}
+
+/// Inicia a thread dos ticks a partir do estado já gravado no banco
+pub fn start_ticker(app: AppHandle, snapshot: TimerSnapshot) -> Ticker {
+    let snapshot = Arc::new(Mutex::new(snapshot));
+    let (wake, rx) = mpsc::channel();
+    let shared = snapshot.clone();
+    std::thread::spawn(move || ticker_loop(app, shared, rx));
+    Ticker { snapshot, wake }
+}
+
+/// Relê a sessão atual do banco após uma transição e avisa as janelas na hora
+pub fn refresh(app: &AppHandle, conn: &Connection) {
+    let snapshot = match Tracker::new(conn).snapshot() {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            eprintln!("Erro ao ler sessão atual: {}", e);
+            return;
+        }
+    };
+    let Some(ticker) = app.try_state::<Ticker>() else {
+        return;
+    };
+    if let Ok(mut current) = ticker.snapshot.lock() {
+        *current = snapshot;
+    }
+    let _ = ticker.wake.send(());
+}
+
+/// Emite um tick por segundo enquanto roda; parado, só acorda numa transição
+fn ticker_loop(app: AppHandle, snapshot: Arc<Mutex<TimerSnapshot>>, rx: Receiver<()>) {
+    let mut last_status = String::from("idle");
+    loop {
+        let Ok(session) = snapshot.lock().map(|s| s.clone()) else {
+            break;
+        };
+        let tick = session.tick(SystemClock.now());
+        let _ = app.emit(TICK_EVENT, &tick);
+        if session.status != last_status {
+            update_tray_icon(&app, &session.status);
+            last_status = session.status.clone();
+        }
+        update_tray_tooltip(&app, &tick);
+
+        let woke = if session.is_running() {
+            rx.recv_timeout(TICK_INTERVAL)
+        } else {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        };
+        if let Err(RecvTimeoutError::Disconnected) = woke {
+            break;
+        }
+    }
+}
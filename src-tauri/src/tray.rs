@@ -3,6 +3,7 @@ use tauri::{
     AppHandle, Manager, Result, WebviewUrl, WebviewWindowBuilder, WindowEvent, menu::{Menu, MenuId, MenuItem}, tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent}
 };
 use tauri::image::Image;
+use tracetimedev_core::ticker::Tick;
 
 pub fn setup_tray(app: &AppHandle) -> Result<()> {
     let quit = MenuItem::with_id(
@@ -58,77 +59,75 @@ pub fn setup_tray(app: &AppHandle) -> Result<()> {
         })
         .on_tray_icon_event(|tray, event| {
             let app = tray.app_handle();
-            match event {
-                TrayIconEvent::Click {
-                    button: MouseButton::Left,
-                    button_state: MouseButtonState::Up,
-                    ..
-                } => {
-                    if let Some(window) = app.get_webview_window("Time") {
-                        let _ = window.unminimize();
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    } else {
-                        let popup_width = 250.0;
-                        let popup_height = 500.0;
-                        let margin = 10.0;
-
-                        let window = tauri::WebviewWindowBuilder::new(
-                            app,
-                            "Time",
-                            tauri::WebviewUrl::App("index.html".into()),
-                        )
-                        .title("TraceTime")
-                        .decorations(false)
-                        .transparent(false)
-                        .always_on_top(true)
-                        .skip_taskbar(true)
-                        .resizable(false)
-                        .inner_size(popup_width, popup_height)
-                        .visible(false)
-                        .build()
-                        .expect("failed to create popup window");
-
-                        let monitor = window
-                            .current_monitor()
-                            .ok()
-                            .flatten()
-                            .expect("Nenhum monitor encontrado");
-
-                        let work_area = monitor.work_area();
-                        let size = window.outer_size().unwrap();
-
-                        let x = work_area.position.x as f64
-                            + work_area.size.width as f64
-                            - size.width as f64
-                            - margin;
-
-                        let y = work_area.position.y as f64
-                            + work_area.size.height as f64
-                            - size.height as f64
-                            - margin;
-
-                        window
-                            .set_position(tauri::PhysicalPosition::new(x as i32, y as i32))
-                            .unwrap();
-
-                        
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                if let Some(window) = app.get_webview_window("Time") {
+                    let _ = window.unminimize();
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                } else {
+                    let popup_width = 250.0;
+                    let popup_height = 500.0;
+                    let margin = 10.0;
 
-                        let window_clone = window.clone();
+                    let window = tauri::WebviewWindowBuilder::new(
+                        app,
+                        "Time",
+                        tauri::WebviewUrl::App("index.html".into()),
+                    )
+                    .title("TraceTime")
+                    .decorations(false)
+                    .transparent(false)
+                    .always_on_top(true)
+                    .skip_taskbar(true)
+                    .resizable(false)
+                    .inner_size(popup_width, popup_height)
+                    .visible(false)
+                    .build()
+                    .expect("failed to create popup window");
 
-                        window.on_window_event(move |event| {
-                            if let tauri::WindowEvent::Focused(false) = event {
-                                let _ = window_clone.hide();
-                            }
-                        });
+                    let monitor = window
+                        .current_monitor()
+                        .ok()
+                        .flatten()
+                        .expect("Nenhum monitor encontrado");
 
-                        
-                        window.show().unwrap();
-                        window.set_focus().unwrap();
+                    let work_area = monitor.work_area();
+                    let size = window.outer_size().unwrap();
+
+                    let x = work_area.position.x as f64
+                        + work_area.size.width as f64
+                        - size.width as f64
+                        - margin;
+
+                    let y = work_area.position.y as f64
+                        + work_area.size.height as f64
+                        - size.height as f64
+                        - margin;
+
+                    window
+                        .set_position(tauri::PhysicalPosition::new(x as i32, y as i32))
+                        .unwrap();
+
+                    
+
+                    let window_clone = window.clone();
+
+                    window.on_window_event(move |event| {
+                        if let tauri::WindowEvent::Focused(false) = event {
+                            let _ = window_clone.hide();
+                        }
+                    });
+
+                    
+                    window.show().unwrap();
+                    window.set_focus().unwrap();
 
-                    }
                 }
-                _ => {}
             }
         })
         .build(app)?;
@@ -153,4 +152,16 @@ pub fn update_tray_icon(app: &tauri::AppHandle, status: &str) {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Tempo corrido e issue da sessão atual no tooltip da bandeja
+pub fn update_tray_tooltip(app: &tauri::AppHandle, tick: &Tick) {
+    if let Some(tray_handle) = app.tray_by_id("tray-main") {
+        let tooltip = match (&tick.session.issue_iid, tick.session.status.as_str()) {
+            (_, "idle") => "TTD".to_string(),
+            (Some(iid), _) => format!("TTD · {} · #{}", tick.elapsed, iid),
+            (None, _) => format!("TTD · {}", tick.elapsed),
+        };
+        let _ = tray_handle.set_tooltip(Some(tooltip));
+    }
+}
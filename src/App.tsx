@@ -1,42 +1,56 @@
 import React, { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "./errors";
+import { subscribe } from "./events";
 import Select from "react-select";
 
-interface Group { id: number; name: string; }
+interface Group { id: number; name: string; profile_id: number; profile_name: string; }
+interface Profile { id: number; name: string; labels: string; gitlab_user: string; gitlab_user_id: number; has_token: boolean; }
 interface Project { id: number; name: string; }
-interface Issue { id: number; title: string; }
+interface Issue { id: number; iid: number; title: string; web_url: string; }
+interface OutboxStatus { pending: number; last_error: string | null; }
+interface IssueFilter { search?: string; assigned_to_me: boolean; state?: string; }
+interface TokenExpiry { profile_id: number; profile_name: string; expires_at: string; days_left: number; }
+interface Recovery { session_id: number; seconds: number; minutes: number; }
+interface IdleBlock { session_id: number; interval_id: number; start_time: number; end_time: number | null; seconds: number; }
+interface Tick { session_id: number | null; status: string; elapsed_seconds: number; elapsed: string; issue_title: string | null; label: string | null; }
 
 const App: React.FC = () => {
   const [groups, setGroups] = useState<Group[]>([]);
   const [projects, setProjects] = useState<Project[]>([]);
   const [issues, setIssues] = useState<Issue[]>([]);
-  const [labels, setLabels] = useState<string[]>([]);
+  const [profiles, setProfiles] = useState<Profile[]>([]);
 
+  const [selectedProfile, setSelectedProfile] = useState<number | null>(null);
   const [selectedGroup, setSelectedGroup] = useState<number | null>(null);
   const [selectedProject, setSelectedProject] = useState<number | null>(null);
   const [selectedIssue, setSelectedIssue] = useState<number | null>(null);
   const [entryType, setEntryType] = useState<string>("");
+  const [onlyMine, setOnlyMine] = useState<boolean>(true);
+  const [issueSearch, setIssueSearch] = useState<string>("");
 
   const [sessionId, setSessionId] = useState<number | null>(null);
   const [status, setStatus] = useState<string>("idle");
   const [preview, setPreview] = useState<string>("0s");
 
   const [configOk, setConfigOk] = useState<boolean>(false);
+  const [outbox, setOutbox] = useState<OutboxStatus>({ pending: 0, last_error: null });
+  const [recovery, setRecovery] = useState<Recovery | null>(null);
+  const [idleBlock, setIdleBlock] = useState<IdleBlock | null>(null);
+  const [expiring, setExpiring] = useState<TokenExpiry[]>([]);
 
-  // Carrega configuração inicial
+  const splitLabels = (labelsStr: string) => labelsStr.split(",").map(l => l.trim()).filter(l => l);
+  const labels = splitLabels(profiles.find(p => p.id === selectedProfile)?.labels ?? "");
+  const groupKey = (profileId: number | null, groupId: number | null) => `${profileId}:${groupId}`;
+
+  // Carrega os perfis prontos para uso (usuário testado e pelo menos um label)
   const checkConfig = async () => {
     try {
-      const [url, token, labelsStr, user, userId] =
-        await invoke<[string, string, string, string, number]>("load_config");
-
-      const hasLabels = labelsStr && labelsStr.split(",").filter(l => l.trim() !== "").length > 0;
-      if (url && token && userId > 0 && user && hasLabels) {
-        setConfigOk(true);
-        setLabels(labelsStr.split(",").map(l => l.trim()).filter(l => l));
-        loadGroups();
-      } else {
-        setConfigOk(false);
-      }
+      const saved = await invoke<Profile[]>("list_profiles");
+      const ready = saved.filter(p => p.has_token && p.gitlab_user_id > 0 && p.gitlab_user && splitLabels(p.labels).length > 0);
+      setProfiles(ready);
+      setConfigOk(ready.length > 0);
+      if (ready.length > 0) loadGroups();
     } catch {
       setConfigOk(false);
     }
@@ -49,29 +63,50 @@ const App: React.FC = () => {
     } catch (err) { console.error("Erro ao carregar grupos:", err); }
   };
 
-  const loadProjects = async (groupId: number) => {
+  const loadProjects = async (profileId: number, groupId: number) => {
     try {
-      const result = await invoke<Project[]>("gitlab_projects", { groupId });
+      const result = await invoke<Project[]>("gitlab_projects", { profileId, groupId });
       setProjects(result);
     } catch (err) { console.error("Erro ao carregar projetos:", err); }
   };
 
-  const loadIssues = async (projectId: number) => {
+  const loadIssues = async (profileId: number, projectId: number) => {
+    const filter: IssueFilter = { assigned_to_me: onlyMine, state: "opened", search: issueSearch };
     try {
-      const result = await invoke<Issue[]>("gitlab_issues", { projectId });
+      const result = await invoke<Issue[]>("gitlab_issues", { profileId, projectId, filter });
       setIssues(result);
     } catch (err) { console.error("Erro ao carregar issues:", err); }
   };
 
+  // Busca issues ao mudar o filtro (com atraso para não buscar a cada tecla)
+  useEffect(() => {
+    if (!selectedProfile || !selectedProject) return;
+    const timeout = setTimeout(() => loadIssues(selectedProfile, selectedProject), 400);
+    return () => clearTimeout(timeout);
+  }, [onlyMine, issueSearch]);
+
+  // Recarrega a lista atualizada em segundo plano pelo cache do backend
+  useEffect(() => {
+    type Refreshed = { profile_id: number; kind: string; parent_id: number | null };
+    return subscribe<Refreshed>("gitlab://refreshed", (payload) => {
+      if (payload.kind === "groups") loadGroups();
+      if (payload.profile_id !== selectedProfile || payload.parent_id === null) return;
+      if (payload.kind === "projects" && payload.parent_id === selectedGroup) loadProjects(payload.profile_id, payload.parent_id);
+      if (payload.kind === "issues" && payload.parent_id === selectedProject) loadIssues(payload.profile_id, payload.parent_id);
+    });
+  }, [selectedProfile, selectedGroup, selectedProject, onlyMine, issueSearch]);
+
   // Recupera última sessão ao abrir
   useEffect(() => {
     checkConfig();
-    invoke<[number, number, number, number, string, string, string] | null>("resume_last_session")
+    invoke<Recovery | null>("get_recovery").then(setRecovery).catch(console.error);
+    invoke<[number, number, number, number, number, string, string, string] | null>("resume_last_session")
       .then((res) => {
         if (res) {
-          const [id, g, p, i, lbl, st, prev] = res;
+          const [id, prof, g, p, i, lbl, st, prev] = res;
           if(st === "finalizado") return;
           setSessionId(id);
+          setSelectedProfile(prof);
           setSelectedGroup(g);
           setSelectedProject(p);
           setSelectedIssue(i);
@@ -82,39 +117,98 @@ const App: React.FC = () => {
       });
   }, []);
 
-  // Atualiza preview a cada 10s se rodando
+  // Acompanha a fila de envio
   useEffect(() => {
-    console.log("Efeito de atualização de preview acionado.");
-    console.log("sessionId:", sessionId, "status:", status);
-    if (!sessionId || status !== "runner") return;
+    invoke<OutboxStatus>("sync_status").then(setOutbox).catch(console.error);
+    return subscribe<OutboxStatus>("sync://outbox", setOutbox);
+  }, []);
 
-    const updatePreview = async () => {
-      const previewTime = await invoke<string>("get_session_time", { sessionId });
-      console.log("Preview atualizado:", previewTime);
-      setPreview(previewTime);
-    };
+  // Avisa sobre tokens perto de expirar
+  useEffect(() => {
+    invoke<TokenExpiry[]>("expiring_tokens").then(setExpiring).catch(console.error);
+    return subscribe<TokenExpiry[]>("token://expiring", setExpiring);
+  }, []);
 
-    updatePreview(); // consulta imediata
+  // O backend emite o tempo corrido da sessão atual a cada segundo e nas transições
+  useEffect(() => {
+    return subscribe<Tick>("timer://tick", (payload) => {
+      setSessionId(payload.session_id);
+      setStatus(payload.status);
+      setPreview(payload.elapsed);
+    });
+  }, []);
 
-    const interval = setInterval(updatePreview, 1000);
-    return () => clearInterval(interval);
-  }, [sessionId, status]);
+  // Tempo sem teclado e mouse separado da sessão, aguardando decisão
+  useEffect(() => {
+    invoke<IdleBlock | null>("get_idle").then(setIdleBlock).catch(console.error);
+    return subscribe<IdleBlock>("idle://detected", setIdleBlock);
+  }, []);
 
+  const handleIdle = async (action: "keep" | "discard" | "reassign") => {
+    if (!idleBlock) return;
+    let decision: object = { action };
+    if (action === "reassign") {
+      const issue = issues.find(i => i.id === selectedIssue);
+      if (!issue || !canStart) return;
+      decision = {
+        action,
+        profile_id: selectedProfile,
+        group_id: selectedGroup,
+        project_id: selectedProject,
+        issue: { id: issue.id, iid: issue.iid, title: issue.title, web_url: issue.web_url },
+        label: entryType,
+      };
+    }
+    try {
+      await invoke("resolve_idle", { intervalId: idleBlock.interval_id, decision });
+      setIdleBlock(null);
+    } catch (err) {
+      console.error("Erro ao resolver tempo ausente:", errorMessage(err));
+    }
+  };
 
-  const canStart = selectedGroup && selectedProject && selectedIssue && entryType;
+  const handleRecovery = async (keep: boolean) => {
+    if (!recovery) return;
+    await invoke("resolve_recovery", { sessionId: recovery.session_id, keep });
+    setRecovery(null);
+  };
+
+  const canStart = selectedProfile && selectedGroup && selectedProject && selectedIssue && entryType;
 
   const handleStart = async () => {
-    const id = await invoke<number>("start_timer", {
-      groupId: selectedGroup,
-      projectId: selectedProject,
-      issueId: selectedIssue,
-      label: entryType,
-    });
-    if (id > 0) {
+    const issue = issues.find(i => i.id === selectedIssue);
+    if (!issue) return;
+    try {
+      const id = await invoke<number>("start_timer", {
+        profileId: selectedProfile,
+        groupId: selectedGroup,
+        projectId: selectedProject,
+        issue: { id: issue.id, iid: issue.iid, title: issue.title, web_url: issue.web_url },
+        label: entryType,
+      });
       setSessionId(id);
       setStatus("runner");
-      const previewTime = await invoke<string>("get_session_time", { sessionId: id });
-      setPreview(previewTime);
+    } catch (err) {
+      console.error("Erro ao iniciar:", errorMessage(err));
+    }
+  };
+
+  // Finaliza ou pausa a sessão atual e já inicia a da issue selecionada
+  const handleSwitch = async () => {
+    const issue = issues.find(i => i.id === selectedIssue);
+    if (!issue) return;
+    try {
+      const id = await invoke<number>("switch_task", {
+        profileId: selectedProfile,
+        groupId: selectedGroup,
+        projectId: selectedProject,
+        issue: { id: issue.id, iid: issue.iid, title: issue.title, web_url: issue.web_url },
+        label: entryType,
+      });
+      setSessionId(id);
+      setStatus("runner");
+    } catch (err) {
+      console.error("Erro ao trocar de tarefa:", errorMessage(err));
     }
   };
 
@@ -122,8 +216,6 @@ const App: React.FC = () => {
     if (!sessionId) return;
     await invoke("pause_timer", { sessionId });
     setStatus("pause");
-    const previewTime = await invoke<string>("get_session_time", { sessionId });
-    setPreview(previewTime);
   };
 
   const handleResume = async () => {
@@ -140,6 +232,10 @@ const App: React.FC = () => {
   };
 
 
+  const currentGroup = groups.find(g => g.profile_id === selectedProfile && g.id === selectedGroup);
+  // com mais de um perfil, o nome do perfil distingue grupos de instâncias diferentes
+  const groupLabel = (g: Group) => profiles.length > 1 ? `${g.profile_name} / ${g.name}` : g.name;
+
   const selectStyles = {
     control: (base: any, state: any) => ({
       ...base,
@@ -184,25 +280,52 @@ const App: React.FC = () => {
     <div className="popup-container">
       {!configOk && (
         <div className="disabled-warning">
-          ⚠️ Configure o GitLab e adicione pelo menos um Label para usar esta tela.
+          ⚠️ Configure um perfil e adicione pelo menos um Label para usar esta tela.
           <button className="retry-btn" onClick={checkConfig}>🔄 Tentar novamente</button>
         </div>
       )}
 
+      {recovery && (
+        <div className="disabled-warning">
+          ⚠️ O app foi fechado com o timer rodando. O intervalo aberto tinha {recovery.minutes} min até o app parar. Manter ou descartar o intervalo inteiro?
+          <div className="recovery-actions">
+            <button className="retry-btn" onClick={() => handleRecovery(true)}>Manter</button>
+            <button className="retry-btn" onClick={() => handleRecovery(false)}>Descartar</button>
+          </div>
+        </div>
+      )}
+
+      {idleBlock && (
+        <div className="disabled-warning">
+          ⏸️ {idleBlock.end_time ? `Você ficou ${Math.round(idleBlock.seconds / 60)} min ausente.` : "Você está ausente."} O que fazer com esse tempo?
+          <div className="recovery-actions">
+            <button className="retry-btn" onClick={() => handleIdle("keep")}>Manter</button>
+            <button className="retry-btn" onClick={() => handleIdle("discard")}>Descartar</button>
+            <button className="retry-btn" onClick={() => handleIdle("reassign")} disabled={!canStart}
+              title="Lança o tempo ausente na issue e tipo selecionados abaixo">
+              Passar para a issue selecionada
+            </button>
+          </div>
+        </div>
+      )}
+
       {/* Grupo */}
       <div className="selector-group">
         <label>Grupo</label>
         <Select
-          options={groups.map(g => ({ value: g.id, label: g.name }))}
-          value={groups.find(g => g.id === selectedGroup) ? { value: selectedGroup, label: groups.find(g => g.id === selectedGroup)?.name } : null}
+          options={groups.map(g => ({ value: groupKey(g.profile_id, g.id), label: groupLabel(g), group: g }))}
+          value={currentGroup ? { value: groupKey(selectedProfile, selectedGroup), label: groupLabel(currentGroup), group: currentGroup } : null}
           onChange={(opt) => {
-            if (opt && opt.value !== null) {
-              setSelectedGroup(opt.value);
+            if (opt) {
+              const { profile_id, id } = opt.group;
+              if (profile_id !== selectedProfile) setEntryType("");
+              setSelectedProfile(profile_id);
+              setSelectedGroup(id);
               setSelectedProject(null);
               setSelectedIssue(null);
               setProjects([]);
               setIssues([]);
-              loadProjects(opt.value);
+              loadProjects(profile_id, id);
             }
           }}
           placeholder="Selecione um grupo..."
@@ -218,11 +341,11 @@ const App: React.FC = () => {
           options={projects.map(p => ({ value: p.id, label: p.name }))}
           value={projects.find(p => p.id === selectedProject) ? { value: selectedProject, label: projects.find(p => p.id === selectedProject)?.name } : null}
           onChange={(opt) => {
-            if (opt && opt.value !== null) {
+            if (opt && opt.value !== null && selectedProfile) {
               setSelectedProject(opt.value);
               setSelectedIssue(null);
               setIssues([]);
-              loadIssues(opt.value);
+              loadIssues(selectedProfile, opt.value);
             }
           }}
           placeholder="Selecione um projeto..."
@@ -233,9 +356,23 @@ const App: React.FC = () => {
 
       {/* Issue */}
       <div className="selector-group">
-        <label>Issue</label>
+        <label>
+          Issue
+          <span className="only-mine">
+            <input
+              type="checkbox"
+              checked={onlyMine}
+              onChange={(e) => setOnlyMine(e.target.checked)}
+            />
+            Só minhas
+          </span>
+        </label>
         <Select
           options={issues.map(i => ({ value: i.id, label: i.title }))}
+          filterOption={null}
+          onInputChange={(text, meta) => {
+            if (meta.action === "input-change") setIssueSearch(text);
+          }}
           value={issues.find(i => i.id === selectedIssue) ? { value: selectedIssue, label: issues.find(i => i.id === selectedIssue)?.title } : null}
           onChange={(opt) => {
             if (opt) {
@@ -276,6 +413,9 @@ const App: React.FC = () => {
         <button onClick={handleStart} disabled={!canStart || status !== "idle"}>
           Iniciar
         </button>
+        <button onClick={handleSwitch} disabled={!canStart || status === "idle"} title="Encerra a sessão atual e inicia esta">
+          Trocar
+        </button>
         <button onClick={handlePause} disabled={status !== "runner"}>
           Pausar
         </button>
@@ -287,7 +427,22 @@ const App: React.FC = () => {
         </button>
       </div>
 
-  
+      {/* Tokens expirando */}
+      {expiring.map((t) => (
+        <div key={t.profile_id} className="sync-pending">
+          🔑 {t.days_left < 0
+            ? `O token de ${t.profile_name} expirou em ${t.expires_at}`
+            : `O token de ${t.profile_name} expira em ${t.expires_at} (${t.days_left} ${t.days_left === 1 ? "dia" : "dias"})`}
+        </div>
+      ))}
+
+      {/* Fila de envio */}
+      {outbox.pending > 0 && (
+        <div className="sync-pending" title={outbox.last_error ?? ""}>
+          ⏳ {outbox.pending} {outbox.pending === 1 ? "lançamento aguardando" : "lançamentos aguardando"} envio
+          <button className="retry-btn" onClick={() => invoke("sync_retry")}>🔄 Enviar agora</button>
+        </div>
+      )}
     </div>
   );
 };
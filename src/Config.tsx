@@ -1,62 +1,218 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "./errors";
+
+interface TokenInfo { name: string; scopes: string[]; expires_at: string | null; }
+interface ConnectionCheck {
+  id: number;
+  username: string;
+  name: string;
+  token: TokenInfo | null;
+  missing_scopes: string[];
+}
+
+interface HttpSettings {
+  ca_certs: string[];
+  client_cert: string | null;
+  client_key: string | null;
+  proxy_url: string | null;
+  no_proxy: string;
+  timeout_secs: number | null;
+  connect_timeout_secs: number | null;
+  accept_invalid_certs: boolean;
+}
+
+interface TimerSettings {
+  switch_action: "stop" | "pause";
+  /** 0 desliga a detecção de ausência */
+  idle_minutes: number;
+  /** Retoma ao desbloquear a sessão pausada pelo bloqueio ou suspensão */
+  auto_resume: boolean;
+}
+
+type BackendKind = "gitlab" | "gitea";
+type AuthKind = "token" | "oauth";
+
+interface Profile {
+  id: number;
+  name: string;
+  backend_kind: BackendKind;
+  url: string;
+  auth_kind: AuthKind;
+  oauth_client_id: string;
+  /** Só na ida: vazio mantém o token guardado */
+  token?: string;
+  /** Só na volta: token mascarado e se há um guardado */
+  token_hint?: string;
+  has_token?: boolean;
+  token_expires_at: string | null;
+  labels: string;
+  gitlab_user: string;
+  gitlab_user_id: number;
+}
 
 function Config() {
+  const [profiles, setProfiles] = useState<Profile[]>([]);
+  const [profileId, setProfileId] = useState(0);
+  const [name, setName] = useState("");
+  const [backendKind, setBackendKind] = useState<BackendKind>("gitlab");
   const [url, setUrl] = useState("");
+  const [authKind, setAuthKind] = useState<AuthKind>("token");
+  const [oauthClientId, setOauthClientId] = useState("");
   const [token, setToken] = useState("");
+  const [tokenHint, setTokenHint] = useState("");
+  const [tokenExpiresAt, setTokenExpiresAt] = useState<string | null>(null);
   const [labels, setLabels] = useState<string[]>([]);
   const [message, setMessage] = useState("");
   const [activeTab, setActiveTab] = useState("gitlab");
   const [newLabel, setNewLabel] = useState("");
+  const [http, setHttp] = useState<HttpSettings | null>(null);
+  const [timer, setTimer] = useState<TimerSettings | null>(null);
 
   // Dados do usuário GitLab
   const [gitlabUser, setGitlabUser] = useState("");
   const [gitlabUserId, setGitlabUserId] = useState<number | null>(null);
 
+  // Preenche o formulário com o perfil (ou limpa, para um perfil novo)
+  const selectProfile = (profile?: Profile) => {
+    setProfileId(profile?.id ?? 0);
+    setName(profile?.name ?? "");
+    setBackendKind(profile?.backend_kind ?? "gitlab");
+    setUrl(profile?.url ?? "");
+    setAuthKind(profile?.auth_kind ?? "token");
+    setOauthClientId(profile?.oauth_client_id ?? "");
+    setToken("");
+    setTokenHint(profile?.has_token ? profile.token_hint ?? "" : "");
+    setTokenExpiresAt(profile?.token_expires_at ?? null);
+    setLabels(profile?.labels ? profile.labels.split(",") : []);
+    setGitlabUser(profile?.gitlab_user ?? "");
+    setGitlabUserId(profile?.gitlab_user_id || null);
+  };
+
+  const loadProfiles = async (selectId?: number) => {
+    try {
+      const saved = await invoke<Profile[]>("list_profiles");
+      setProfiles(saved);
+      selectProfile(saved.find((p) => p.id === selectId) ?? saved[0]);
+    } catch (err) {
+      setMessage(errorMessage(err));
+    }
+  };
+
   useEffect(() => {
-    const load = async () => {
-      try {
-        const [savedUrl, savedToken, savedLabels, savedUser, savedUserId] =
-          await invoke<[string, string, string, string, number]>("load_config");
-
-        setUrl(savedUrl);
-        setToken(savedToken);
-        setLabels(savedLabels ? savedLabels.split(",") : []);
-        setGitlabUser(savedUser);
-        setGitlabUserId(savedUserId);
-      } catch {
-        console.log("Nenhuma configuração encontrada");
-      }
-    };
-    load();
+    loadProfiles();
+    invoke<HttpSettings>("load_http_settings").then(setHttp).catch((err) => setMessage(errorMessage(err)));
+    invoke<TimerSettings>("load_timer_settings").then(setTimer).catch((err) => setMessage(errorMessage(err)));
   }, []);
 
   const test = async () => {
     try {
-      const [username, id] = await invoke<[string, number]>("test_gitlab", {
+      const { username, id, token: info, missing_scopes } = await invoke<ConnectionCheck>("test_gitlab", {
         url,
-        token,
+        token: authKind === "token" ? token : "",
+        backendKind,
+        profileId: profileId || null,
       });
       setGitlabUser(username);
       setGitlabUserId(id);
-      setMessage(`Conexão OK! Usuário: ${username} (ID: ${id})`);
+      setTokenExpiresAt(info?.expires_at ?? null);
+      const details = info
+        ? ` Escopos: ${info.scopes.join(", ") || "nenhum"}. Expira: ${info.expires_at ?? "nunca"}.`
+        : "";
+      const missing = missing_scopes.length > 0
+        ? ` ⚠️ Faltam os escopos ${missing_scopes.join(", ")}: não será possível lançar tempo.`
+        : "";
+      setMessage(`Conexão OK! Usuário: ${username} (ID: ${id}).${details}${missing}`);
     } catch (err) {
-      setMessage("Falha ao conectar no GitLab. Verifique URL/Token.");
+      setMessage(`Falha ao conectar. ${errorMessage(err)}`);
       setGitlabUser("");
       setGitlabUserId(null);
     }
   };
 
+  const currentProfile = (): Profile => ({
+    id: profileId,
+    name,
+    backend_kind: backendKind,
+    url,
+    auth_kind: authKind,
+    oauth_client_id: oauthClientId,
+    token: authKind === "token" ? token : "",
+    labels: labels.join(","),
+    gitlab_user: gitlabUser,
+    gitlab_user_id: gitlabUserId ?? 0,
+    token_expires_at: authKind === "token" ? tokenExpiresAt : null,
+  });
+
+  // Abre o login do GitLab no navegador e espera o retorno
+  const oauthLogin = async () => {
+    setMessage("Conclua o login no navegador...");
+    try {
+      const id = await invoke<number>("gitlab_oauth_login", { profile: currentProfile() });
+      await loadProfiles(id);
+      setMessage("Login concluído!");
+    } catch (err) {
+      setMessage(`Falha no login. ${errorMessage(err)}`);
+    }
+  };
+
   const save = async () => {
-    const labelsString = labels.join(",");
-    await invoke("save_config", {
-      url,
-      token,
-      labels: labelsString,
-      gitlabUser,
-      gitlabUserId,
-    });
-    setMessage("Configuração salva com sucesso!");
+    const profile = currentProfile();
+    try {
+      const id = await invoke<number>("save_profile", { profile });
+      await loadProfiles(id);
+      setMessage("Configuração salva com sucesso!");
+    } catch (err) {
+      setMessage(errorMessage(err));
+    }
+    setTimeout(() => setMessage(""), 3000);
+  };
+
+  const removeProfile = async () => {
+    if (!profileId) return;
+    try {
+      await invoke("delete_profile", { profileId });
+      await loadProfiles();
+      setMessage("Perfil removido.");
+    } catch (err) {
+      setMessage(errorMessage(err));
+    }
+    setTimeout(() => setMessage(""), 3000);
+  };
+
+  const saveHttp = async () => {
+    if (!http) return;
+    try {
+      await invoke("save_http_settings", { settings: http });
+      setMessage("Configuração de rede salva!");
+    } catch (err) {
+      setMessage(errorMessage(err));
+    }
+    setTimeout(() => setMessage(""), 3000);
+  };
+
+  const saveTimer = async () => {
+    if (!timer) return;
+    try {
+      await invoke("save_timer_settings", { settings: timer });
+      setMessage("Preferências do timer salvas!");
+    } catch (err) {
+      setMessage(errorMessage(err));
+    }
+    setTimeout(() => setMessage(""), 3000);
+  };
+
+  // Campo opcional: vazio vira null
+  const optional = (value: string) => (value.trim() === "" ? null : value);
+  const seconds = (value: string) => (value.trim() === "" ? null : Number(value));
+
+  const purgeCache = async () => {
+    try {
+      await invoke("gitlab_cache_purge");
+      setMessage("Cache limpo.");
+    } catch (err) {
+      setMessage(errorMessage(err));
+    }
     setTimeout(() => setMessage(""), 3000);
   };
 
@@ -78,7 +234,7 @@ function Config() {
           className={activeTab === "gitlab" ? "active" : ""}
           onClick={() => setActiveTab("gitlab")}
         >
-          GitLab
+          Perfis
         </button>
         <button
           className={activeTab === "labels" ? "active" : ""}
@@ -87,29 +243,96 @@ function Config() {
         >
           Tipos/Labels
         </button>
+        <button
+          className={activeTab === "network" ? "active" : ""}
+          onClick={() => setActiveTab("network")}
+        >
+          Rede
+        </button>
+        <button
+          className={activeTab === "timer" ? "active" : ""}
+          onClick={() => setActiveTab("timer")}
+        >
+          Timer
+        </button>
       </div>
 
       <div className="content">
         {activeTab === "gitlab" && (
           <div className="form">
+            <div className="form-group" style={{ display: "flex", gap: "8px" }}>
+              <select value={profileId}
+                onChange={(e) => selectProfile(profiles.find((p) => p.id === Number(e.target.value)))}>
+                {profiles.map((p) => (
+                  <option key={p.id} value={p.id}>{p.name}</option>
+                ))}
+                <option value={0}>➕ Novo perfil</option>
+              </select>
+              <button className="icon-btn remove" onClick={removeProfile} disabled={!profileId}>
+                🗑️
+              </button>
+            </div>
             <div className="form-group">
-              <label>URL:</label>
-              <input value={url} 
-                onChange={(e) => {    
-                  setUrl(e.target.value);
+              <label>Nome:</label>
+              <input value={name} placeholder={url || "Ex.: GitLab da empresa"}
+                onChange={(e) => setName(e.target.value)} />
+            </div>
+            <div className="form-group">
+              <label>Rastreador:</label>
+              <select value={backendKind}
+                onChange={(e) => {
+                  setBackendKind(e.target.value as BackendKind);
+                  if (e.target.value !== "gitlab") setAuthKind("token");
                   setGitlabUser("");
                   setGitlabUserId(null);
-                }} />
+                }}>
+                <option value="gitlab">GitLab</option>
+                <option value="gitea">Gitea / Forgejo</option>
+              </select>
             </div>
             <div className="form-group">
-              <label>Access Token:</label>
-              <input value={token} 
-                onChange={(e) => {
-                  setToken(e.target.value);
+              <label>URL:</label>
+              <input value={url} 
+                onChange={(e) => {    
+                  setUrl(e.target.value);
                   setGitlabUser("");
                   setGitlabUserId(null);
                 }} />
             </div>
+            {backendKind === "gitlab" && (
+              <div className="form-group">
+                <label>Autenticação:</label>
+                <select value={authKind}
+                  onChange={(e) => {
+                    setAuthKind(e.target.value as AuthKind);
+                    setGitlabUser("");
+                    setGitlabUserId(null);
+                  }}>
+                  <option value="token">Access Token</option>
+                  <option value="oauth">Login no navegador (OAuth)</option>
+                </select>
+              </div>
+            )}
+            {authKind === "oauth" && backendKind === "gitlab" ? (
+              <div className="form-group" style={{ display: "flex", gap: "8px" }}>
+                <input value={oauthClientId} placeholder="Application ID"
+                  onChange={(e) => setOauthClientId(e.target.value)} />
+                <button onClick={oauthLogin} disabled={!url || !oauthClientId}>
+                  🔑 Entrar
+                </button>
+              </div>
+            ) : (
+              <div className="form-group">
+                <label>Access Token:</label>
+                <input value={token} type="password"
+                  placeholder={tokenHint || "Cole o token de acesso"}
+                  onChange={(e) => {
+                    setToken(e.target.value);
+                    setGitlabUser("");
+                    setGitlabUserId(null);
+                  }} />
+              </div>
+            )}
             <div className="form-group">
               <label>Usuário:</label>
               <input value={gitlabUser} readOnly />
@@ -130,6 +353,91 @@ function Config() {
                 💾 Salvar
               </button>
             </div>
+            <div className="buttons">
+              <button onClick={purgeCache}>🧹 Limpar cache</button>
+            </div>
+            {message && <p className="success">{message}</p>}
+          </div>
+        )}
+
+        {activeTab === "network" && http && (
+          <div className="form">
+            <div className="form-group">
+              <label>Certificados raiz extras (PEM, um caminho por linha):</label>
+              <textarea value={http.ca_certs.join("\n")} rows={2}
+                onChange={(e) => setHttp({ ...http, ca_certs: e.target.value.split("\n") })} />
+            </div>
+            <div className="form-group">
+              <label>Certificado do cliente (PEM):</label>
+              <input value={http.client_cert ?? ""}
+                onChange={(e) => setHttp({ ...http, client_cert: optional(e.target.value) })} />
+            </div>
+            <div className="form-group">
+              <label>Chave do certificado (PEM):</label>
+              <input value={http.client_key ?? ""}
+                onChange={(e) => setHttp({ ...http, client_key: optional(e.target.value) })} />
+            </div>
+            <div className="form-group">
+              <label>Proxy:</label>
+              <input value={http.proxy_url ?? ""} placeholder="http://proxy.empresa:3128"
+                onChange={(e) => setHttp({ ...http, proxy_url: optional(e.target.value) })} />
+            </div>
+            <div className="form-group">
+              <label>Sem proxy para:</label>
+              <input value={http.no_proxy} placeholder="localhost, .empresa.local"
+                onChange={(e) => setHttp({ ...http, no_proxy: e.target.value })} />
+            </div>
+            <div className="form-group" style={{ display: "flex", gap: "8px" }}>
+              <input type="number" min={1} value={http.timeout_secs ?? ""} placeholder="Timeout (30s)"
+                onChange={(e) => setHttp({ ...http, timeout_secs: seconds(e.target.value) })} />
+              <input type="number" min={1} value={http.connect_timeout_secs ?? ""} placeholder="Conexão (10s)"
+                onChange={(e) => setHttp({ ...http, connect_timeout_secs: seconds(e.target.value) })} />
+            </div>
+            <div className="form-group">
+              <label>
+                <input type="checkbox" checked={http.accept_invalid_certs}
+                  onChange={(e) => setHttp({ ...http, accept_invalid_certs: e.target.checked })} />
+                Aceitar certificados inválidos
+              </label>
+              {http.accept_invalid_certs && (
+                <p className="warning">
+                  ⚠️ PERIGOSO: sem verificar o certificado, qualquer um na rede pode se passar pelo
+                  servidor e capturar seu token. Use só enquanto a CA interna não estiver configurada.
+                </p>
+              )}
+            </div>
+            <div className="buttons">
+              <button onClick={saveHttp}>💾 Salvar</button>
+            </div>
+            {message && <p className="success">{message}</p>}
+          </div>
+        )}
+
+        {activeTab === "timer" && timer && (
+          <div className="form">
+            <div className="form-group">
+              <label>Ao trocar de tarefa, a sessão atual:</label>
+              <select value={timer.switch_action}
+                onChange={(e) => setTimer({ ...timer, switch_action: e.target.value as TimerSettings["switch_action"] })}>
+                <option value="stop">É finalizada e enviada</option>
+                <option value="pause">Fica pausada</option>
+              </select>
+            </div>
+            <div className="form-group">
+              <label>Perguntar sobre o tempo ausente após (minutos, 0 desliga):</label>
+              <input type="number" min={0} value={timer.idle_minutes}
+                onChange={(e) => setTimer({ ...timer, idle_minutes: Math.max(0, Number(e.target.value) || 0) })} />
+            </div>
+            <div className="form-group">
+              <label>
+                <input type="checkbox" checked={timer.auto_resume}
+                  onChange={(e) => setTimer({ ...timer, auto_resume: e.target.checked })} />
+                {" "}Retomar ao desbloquear a tela (o timer sempre pausa ao bloquear ou suspender)
+              </label>
+            </div>
+            <div className="buttons">
+              <button onClick={saveTimer}>💾 Salvar</button>
+            </div>
             {message && <p className="success">{message}</p>}
           </div>
         )}
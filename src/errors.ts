@@ -0,0 +1,33 @@
+// Erro devolvido pelos comandos do backend (ver src-tauri/core/src/error.rs)
+export interface AppError {
+  kind:
+    | "database"
+    | "network"
+    | "auth"
+    | "not_configured"
+    | "gitlab"
+    | "invalid_state"
+    | "not_found"
+    | "internal";
+  message: string;
+  status?: number;
+  body?: string;
+}
+
+export function isAppError(err: unknown): err is AppError {
+  return typeof err === "object" && err !== null && "kind" in err && "message" in err;
+}
+
+export function errorMessage(err: unknown): string {
+  if (!isAppError(err)) return String(err);
+  switch (err.kind) {
+    case "auth":
+      return "Token inválido ou sem permissão. Verifique o Access Token.";
+    case "network":
+      return "Não foi possível conectar no servidor. Verifique a URL e a rede.";
+    case "not_configured":
+      return "Configure o rastreador antes de continuar.";
+    default:
+      return err.message;
+  }
+}
@@ -0,0 +1,10 @@
+import { listen } from "@tauri-apps/api/event";
+import { errorMessage } from "./errors";
+
+// Ouve um evento do backend e devolve a limpeza para o useEffect.
+// Se a janela não tiver permissão (capabilities), o erro aparece no console em vez de sumir.
+export function subscribe<T>(event: string, handler: (payload: T) => void): () => void {
+  const unlisten = listen<T>(event, ({ payload }) => handler(payload));
+  unlisten.catch((err) => console.error(`Não foi possível ouvir ${event}:`, errorMessage(err)));
+  return () => { unlisten.then((fn) => fn()).catch(() => {}); };
+}